@@ -14,7 +14,7 @@ async fn main() {
         println!("usage : {:?} <username>", args[0]);
         return;
     }
-    let bridge = hueclient::Bridge::discover()
+    let bridge = hueclient::Bridge::discover(Default::default())
         .await
         .unwrap()
         .with_user(args[1].to_string());
@@ -1,4 +1,5 @@
 extern crate hueclient;
+use hueclient::color::{Kelvin, Mirek};
 use std::env;
 
 #[allow(dead_code)]
@@ -29,15 +30,8 @@ async fn main() {
                     0, // sat
                     l.color_temperature
                         .as_ref()
-                        .map(|ct| if let Some(mirek) = ct.mirek {
-                            if mirek != 0 {
-                                1000000u32 / (mirek as u32)
-                            } else {
-                                0
-                            }
-                        } else {
-                            0
-                        })
+                        .and_then(|ct| ct.mirek)
+                        .map(|mirek| Kelvin::from(Mirek(mirek)).0)
                         .unwrap_or(0),
                     l.color.as_ref().map(|color| color.xy.x).unwrap_or(0.0),
                     l.color.as_ref().map(|color| color.xy.y).unwrap_or(0.0),
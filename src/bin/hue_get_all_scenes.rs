@@ -17,9 +17,15 @@ async fn main() {
         .with_user(args[1].to_string());
     match bridge.get_all_scenes().await {
         Ok(scenes) => {
-            println!("id                                   name");
+            println!("id                                   name                                     group                                active");
             for l in scenes.iter() {
-                println!("{:2} {:40}", l.id, l.metadata.name,);
+                println!(
+                    "{:2} {:40} {:36} {}",
+                    l.id,
+                    l.metadata.name,
+                    l.group.rid,
+                    l.is_active()
+                );
             }
         }
         Err(err) => {
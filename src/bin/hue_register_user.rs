@@ -1,9 +1,7 @@
 extern crate hueclient;
-use hueclient::HueError;
 use std::env;
+use std::time::Duration;
 
-#[allow(while_true)]
-#[allow(dead_code)]
 #[tokio::main]
 async fn main() {
     #[cfg(feature = "pretty_env_logger")]
@@ -15,20 +13,25 @@ async fn main() {
     } else {
         let bridge = hueclient::Bridge::discover_required().await;
         println!("posting user {:?} in {:?}", args[1], bridge);
-        loop {
-            let r = bridge.clone().register_application(&args[1]).await;
-            match r {
-                Ok(r) => {
-                    eprint!("done: ");
-                    println!("{}", r.application_key);
-                    break;
-                }
-                Err(HueError::BridgeError { code: 101, .. }) => {
+        let mut prompted = false;
+        let r = bridge
+            .register_application_with_retry(&args[1], Duration::from_secs(60), |_| {
+                if !prompted {
                     println!("Push the bridge button");
-                    std::thread::sleep(::std::time::Duration::from_secs(5));
+                    prompted = true;
+                }
+            })
+            .await;
+        match r {
+            Ok(r) => {
+                eprint!("done: ");
+                println!("{}", r.application_key);
+                if let Some(client_key) = r.client_key {
+                    eprint!("client key: ");
+                    println!("{client_key}");
                 }
-                Err(e) => panic!("error {e}"),
             }
+            Err(e) => panic!("error {e}"),
         }
     }
 }
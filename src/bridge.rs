@@ -1,7 +1,15 @@
+use crate::color::{Kelvin, Mirek};
+use crate::tls::BridgeIdentity;
+use crate::{
+    CertificatePolicy, CommandCreateEntertainmentConfiguration,
+    CommandUpdateEntertainmentConfiguration, DiscoveredBridge, DiscoveryOptions,
+    EntertainmentConfiguration, EntertainmentSegment, EntertainmentService, EventStreamOptions,
+    HueEvent, Resync,
+};
 use futures::Stream;
 use futures::StreamExt;
 use reqwest::Method;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
@@ -56,6 +64,30 @@ pub struct MirekSchema {
     pub mirek_maximum: u16,
 }
 
+impl MirekSchema {
+    /// Returns whether the light accepts this color temperature.
+    pub fn contains(&self, mirek: Mirek) -> bool {
+        (self.mirek_minimum..=self.mirek_maximum).contains(&mirek.0)
+    }
+
+    /// Returns the closest color temperature the light accepts.
+    pub fn clamp(&self, mirek: Mirek) -> Mirek {
+        Mirek(mirek.0.clamp(self.mirek_minimum, self.mirek_maximum))
+    }
+
+    /// Returns the color temperature, or an error if the light does not accept it.
+    pub fn validate(&self, mirek: Mirek) -> crate::Result<Mirek> {
+        if self.contains(mirek) {
+            Ok(mirek)
+        } else {
+            Err(crate::HueError::invalid_command(format!(
+                "color temperature of {} mirek outside of {}..={}",
+                mirek.0, self.mirek_minimum, self.mirek_maximum
+            )))
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColorTemperature {
     pub mirek: Option<u16>,
@@ -81,6 +113,189 @@ pub struct Color {
     pub gamut: Option<Gamut>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LightEffect {
+    NoEffect,
+    Candle,
+    Fire,
+    Prism,
+    Sparkle,
+    Opal,
+    Glisten,
+    Underwater,
+    Cosmos,
+    Sunbeam,
+    Enchant,
+    /// An effect not known to this version of the library.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightEffects {
+    pub status: Option<LightEffect>,
+    #[serde(default)]
+    pub effect_values: Vec<LightEffect>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimedEffect {
+    NoEffect,
+    Sunrise,
+    Sunset,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightTimedEffects {
+    pub status: Option<TimedEffect>,
+    #[serde(default)]
+    pub effect_values: Vec<TimedEffect>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Signal {
+    NoSignal,
+    /// Toggles the light on and off.
+    OnOff,
+    /// Toggles between the first color and off.
+    OnOffColor,
+    /// Alternates between the two given colors.
+    Alternating,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightSignaling {
+    #[serde(default)]
+    pub signal_values: Vec<Signal>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertAction {
+    Breathe,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightAlert {
+    #[serde(default)]
+    pub action_values: Vec<AlertAction>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradientMode {
+    InterpolatedPalette,
+    InterpolatedPaletteMirrored,
+    RandomPixelated,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GradientPoint {
+    pub color: CommandLightColor,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gradient {
+    #[serde(default)]
+    pub points: Vec<GradientPoint>,
+    pub mode: Option<GradientMode>,
+    /// Maximum number of points this light accepts in a gradient.
+    pub points_capable: u8,
+    #[serde(default)]
+    pub mode_values: Vec<GradientMode>,
+    /// Number of individually addressable pixels of the light.
+    pub pixel_count: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerupPreset {
+    /// Bright warm white, the factory default.
+    Safety,
+    /// Restores the state from before the power failure.
+    Powerfail,
+    /// Restores the state from the last time the light was switched on.
+    LastOnState,
+    /// Uses the `on`, `dimming` and `color` settings of the powerup configuration.
+    Custom,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerupOnMode {
+    On,
+    Toggle,
+    Previous,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerupOn {
+    pub mode: PowerupOnMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<On>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerupDimmingMode {
+    Dimming,
+    Previous,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerupDimming {
+    pub mode: PowerupDimmingMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimming: Option<CommandLightDimming>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerupColorMode {
+    ColorTemperature,
+    Color,
+    Previous,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerupColor {
+    pub mode: PowerupColorMode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_temperature: Option<CommandLightColorTemperature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<CommandLightColor>,
+}
+
+/// What a light does when it gets powered on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Powerup {
+    pub preset: PowerupPreset,
+    /// Whether the powerup settings are stored on the light itself.
+    pub configured: bool,
+    pub on: Option<PowerupOn>,
+    pub dimming: Option<PowerupDimming>,
+    pub color: Option<PowerupColor>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Light {
     pub id: String,
@@ -91,6 +306,305 @@ pub struct Light {
     pub dimming: Option<Dimming>,
     pub color_temperature: Option<ColorTemperature>,
     pub color: Option<Color>,
+    pub effects: Option<LightEffects>,
+    pub timed_effects: Option<LightTimedEffects>,
+    pub signaling: Option<LightSignaling>,
+    pub alert: Option<LightAlert>,
+    pub gradient: Option<Gradient>,
+    pub powerup: Option<Powerup>,
+}
+
+impl Light {
+    /// Approximates the current color of the light in 8-bit sRGB, for instance to show a swatch.
+    /// Uses the color temperature when it is valid, the xy color otherwise, and plain white for
+    /// lights without colors. An off light is black.
+    pub fn approximate_rgb(&self) -> (u8, u8, u8) {
+        if !self.on.on {
+            return (0, 0, 0);
+        }
+        let brightness = self.dimming.as_ref().map_or(100.0, |d| d.brightness);
+        let mirek = self
+            .color_temperature
+            .as_ref()
+            .filter(|ct| ct.mirek_valid)
+            .and_then(|ct| ct.mirek);
+        match (mirek, &self.color) {
+            (Some(mirek), _) => crate::color::mirek_to_rgb(mirek, brightness),
+            (None, Some(color)) => crate::color::xy_to_rgb(&color.xy, brightness),
+            (None, None) => {
+                let white = (brightness / 100.0 * 255.0).round().clamp(0.0, 255.0) as u8;
+                (white, white, white)
+            }
+        }
+    }
+
+    /// Returns the range of color temperatures this light accepts, if it supports color
+    /// temperatures at all.
+    pub fn mirek_schema(&self) -> Option<&MirekSchema> {
+        self.color_temperature.as_ref().map(|ct| &ct.mirek_schema)
+    }
+
+    /// Returns the range of colors this light can show, if it supports colors at all.
+    pub fn gamut(&self) -> Option<&Gamut> {
+        self.color.as_ref().and_then(|color| color.gamut.as_ref())
+    }
+
+    /// Returns the effects this light supports, empty if it supports none.
+    pub fn supported_effects(&self) -> &[LightEffect] {
+        self.effects
+            .as_ref()
+            .map_or(&[], |effects| &effects.effect_values)
+    }
+
+    pub fn supported_timed_effects(&self) -> &[TimedEffect] {
+        self.timed_effects
+            .as_ref()
+            .map_or(&[], |effects| &effects.effect_values)
+    }
+
+    pub fn supported_signals(&self) -> &[Signal] {
+        self.signaling
+            .as_ref()
+            .map_or(&[], |signaling| &signaling.signal_values)
+    }
+
+    pub fn supported_alerts(&self) -> &[AlertAction] {
+        self.alert
+            .as_ref()
+            .map_or(&[], |alert| &alert.action_values)
+    }
+
+    /// Returns a command that would put a light in the current state of this one. The color
+    /// temperature is used when it is valid, the xy color otherwise.
+    pub fn to_command(&self) -> CommandLight {
+        let mut command = if self.on.on {
+            CommandLight::default().on()
+        } else {
+            CommandLight::default().off()
+        };
+        if let Some(dimming) = &self.dimming {
+            command = command.with_brightness(dimming.brightness);
+        }
+        match (&self.color_temperature, &self.color) {
+            (
+                Some(ColorTemperature {
+                    mirek: Some(mirek),
+                    mirek_valid: true,
+                    ..
+                }),
+                _,
+            ) => command.with_mirek(*mirek),
+            (_, Some(color)) => command.with_xy(color.xy.x, color.xy.y),
+            _ => command,
+        }
+    }
+}
+
+/// The aggregated light state of a room, a zone or the whole home.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupedLight {
+    pub id: String,
+    pub id_v1: Option<String>,
+    /// The room, zone or bridge_home this grouped light belongs to.
+    pub owner: ResourceIdentifier,
+    /// On if at least one light in the group is on.
+    pub on: Option<On>,
+    /// Average brightness of the lights in the group that are on.
+    pub dimming: Option<Dimming>,
+}
+
+fn find_grouped_light(services: &[ResourceIdentifier]) -> Option<&str> {
+    services
+        .iter()
+        .find(|service| service.rtype == "grouped_light")
+        .map(|service| service.rid.as_str())
+}
+
+fn average_brightness(lights: &[Light]) -> Option<f32> {
+    let brightnesses: Vec<f32> = lights
+        .iter()
+        .filter(|light| light.on.on)
+        .filter_map(|light| light.dimming.as_ref().map(|d| d.brightness))
+        .collect();
+    if brightnesses.is_empty() {
+        None
+    } else {
+        Some(brightnesses.iter().sum::<f32>() / brightnesses.len() as f32)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionReport {
+    /// Last time the value of this property changed.
+    pub changed: String,
+    pub motion: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionState {
+    pub motion: Option<bool>,
+    pub motion_valid: bool,
+    pub motion_report: Option<MotionReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Motion {
+    pub id: String,
+    pub id_v1: Option<String>,
+    /// The device owning this sensor service.
+    pub owner: ResourceIdentifier,
+    pub enabled: bool,
+    pub motion: MotionState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureReport {
+    /// Last time the value of this property changed.
+    pub changed: String,
+    /// Temperature in degrees Celsius.
+    pub temperature: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureState {
+    pub temperature: Option<f32>,
+    pub temperature_valid: bool,
+    pub temperature_report: Option<TemperatureReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Temperature {
+    pub id: String,
+    pub id_v1: Option<String>,
+    /// The device owning this sensor service.
+    pub owner: ResourceIdentifier,
+    pub enabled: bool,
+    pub temperature: TemperatureState,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightLevelReport {
+    /// Last time the value of this property changed.
+    pub changed: String,
+    /// Light level in 10000*log10(lux) + 1.
+    pub light_level: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightLevelState {
+    pub light_level: Option<u32>,
+    pub light_level_valid: bool,
+    pub light_level_report: Option<LightLevelReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightLevel {
+    pub id: String,
+    pub id_v1: Option<String>,
+    /// The device owning this sensor service.
+    pub owner: ResourceIdentifier,
+    pub enabled: bool,
+    pub light: LightLevelState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonEvent {
+    InitialPress,
+    Repeat,
+    ShortRelease,
+    LongRelease,
+    DoubleShortRelease,
+    LongPress,
+    /// An event value not known to this version of the library.
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonReport {
+    /// Last time the value of this property changed.
+    pub updated: String,
+    pub event: ButtonEvent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonState {
+    pub last_event: Option<ButtonEvent>,
+    pub button_report: Option<ButtonReport>,
+    /// Duration in ms between two `repeat` events while the button is held down.
+    pub repeat_interval: Option<u32>,
+    pub event_values: Option<Vec<ButtonEvent>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonMetadata {
+    /// Position of the button on the device, starting at 1.
+    pub control_id: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Button {
+    pub id: String,
+    pub id_v1: Option<String>,
+    /// The device owning this button service.
+    pub owner: ResourceIdentifier,
+    pub metadata: ButtonMetadata,
+    pub button: ButtonState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotaryAction {
+    Start,
+    Repeat,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RotationDirection {
+    ClockWise,
+    CounterClockWise,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rotation {
+    pub direction: RotationDirection,
+    /// Amount of rotation since the previous event.
+    pub steps: u32,
+    /// Duration of the rotation in ms.
+    pub duration: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotaryEvent {
+    pub action: RotaryAction,
+    pub rotation: Rotation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RotaryReport {
+    /// Last time the value of this property changed.
+    pub updated: String,
+    pub action: RotaryAction,
+    pub rotation: Rotation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelativeRotaryState {
+    pub last_event: Option<RotaryEvent>,
+    pub rotary_report: Option<RotaryReport>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelativeRotary {
+    pub id: String,
+    pub id_v1: Option<String>,
+    /// The device owning this rotary service.
+    pub owner: ResourceIdentifier,
+    pub relative_rotary: RelativeRotaryState,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -108,151 +622,892 @@ pub struct Room {
     pub services: Vec<ResourceIdentifier>,
 }
 
+impl Room {
+    /// Returns the id of the grouped_light service controlling all the lights of this room.
+    pub fn grouped_light_id(&self) -> Option<&str> {
+        find_grouped_light(&self.services)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedRoom {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub metadata: Metadata,
+    pub children: Vec<Light>,
+    pub services: Vec<ResourceIdentifier>,
+}
+
+impl ResolvedRoom {
+    /// Returns the id of the grouped_light service controlling all the lights of this room.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// for room in &bridge.resolve_all_rooms().await.unwrap() {
+    ///     if let Some(group) = room.grouped_light_id() {
+    ///         let cmd = hueclient::CommandLight::default().off();
+    ///         bridge.set_group_state(group, &cmd).await.unwrap();
+    ///     }
+    /// }
+    /// # })
+    /// ```
+    pub fn grouped_light_id(&self) -> Option<&str> {
+        find_grouped_light(&self.services)
+    }
+
+    /// Returns true if at least one light of this room is on.
+    pub fn any_on(&self) -> bool {
+        self.children.iter().any(|light| light.on.on)
+    }
+
+    /// Returns the average brightness of the lights of this room that are on, or `None` if none
+    /// of them is on and dimmable.
+    pub fn average_brightness(&self) -> Option<f32> {
+        average_brightness(&self.children)
+    }
+
+    /// Builds a scene capturing the current state of every light in this room.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// for room in &bridge.resolve_all_rooms().await.unwrap() {
+    ///     let scene = room.snapshot_scene("Good settings");
+    ///     bridge.create_scene(&scene).await.unwrap();
+    /// }
+    /// # })
+    /// ```
+    pub fn snapshot_scene(&self, name: impl Into<String>) -> CommandCreateScene {
+        let group = ResourceIdentifier {
+            rid: self.id.clone(),
+            rtype: "room".to_string(),
+        };
+        self.children
+            .iter()
+            .fold(CommandCreateScene::new(name, group), |scene, light| {
+                scene.with_action(&light.id, light.to_command())
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Zone {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub metadata: Metadata,
+    pub children: Vec<ResourceIdentifier>,
+    pub services: Vec<ResourceIdentifier>,
+}
+
+impl Zone {
+    /// Returns the id of the grouped_light service controlling all the lights of this zone.
+    pub fn grouped_light_id(&self) -> Option<&str> {
+        find_grouped_light(&self.services)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolvedZone {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub metadata: Metadata,
+    pub children: Vec<Light>,
+    pub services: Vec<ResourceIdentifier>,
+}
+
+impl ResolvedZone {
+    /// Returns the id of the grouped_light service controlling all the lights of this zone.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// for zone in &bridge.resolve_all_zones().await.unwrap() {
+    ///     if let Some(group) = zone.grouped_light_id() {
+    ///         let cmd = hueclient::CommandLight::default().off();
+    ///         bridge.set_group_state(group, &cmd).await.unwrap();
+    ///     }
+    /// }
+    /// # })
+    /// ```
+    pub fn grouped_light_id(&self) -> Option<&str> {
+        find_grouped_light(&self.services)
+    }
+
+    /// Returns true if at least one light of this zone is on.
+    pub fn any_on(&self) -> bool {
+        self.children.iter().any(|light| light.on.on)
+    }
+
+    /// Returns the average brightness of the lights of this zone that are on, or `None` if none
+    /// of them is on and dimmable.
+    pub fn average_brightness(&self) -> Option<f32> {
+        average_brightness(&self.children)
+    }
+
+    /// Builds a scene capturing the current state of every light in this zone.
+    pub fn snapshot_scene(&self, name: impl Into<String>) -> CommandCreateScene {
+        let group = ResourceIdentifier {
+            rid: self.id.clone(),
+            rtype: "zone".to_string(),
+        };
+        self.children
+            .iter()
+            .fold(CommandCreateScene::new(name, group), |scene, light| {
+                scene.with_action(&light.id, light.to_command())
+            })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommandGroupMetadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archetype: Option<String>,
+}
+
+/// Describes the changes to apply to a room or zone with `Bridge::update_room` or
+/// `Bridge::update_zone`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommandGroup {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<CommandGroupMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<ResourceIdentifier>>,
+}
+
+impl CommandGroup {
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self {
+            metadata: Some(CommandGroupMetadata {
+                name: Some(name.into()),
+                ..self.metadata.unwrap_or_default()
+            }),
+            ..self
+        }
+    }
+
+    pub fn with_archetype(self, archetype: impl Into<String>) -> Self {
+        Self {
+            metadata: Some(CommandGroupMetadata {
+                archetype: Some(archetype.into()),
+                ..self.metadata.unwrap_or_default()
+            }),
+            ..self
+        }
+    }
+
+    /// Replaces the children of the group. Devices for a room, lights for a zone.
+    pub fn with_children(self, children: Vec<ResourceIdentifier>) -> Self {
+        Self {
+            children: Some(children),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CreateGroup<'a> {
+    metadata: &'a Metadata,
+    children: &'a [ResourceIdentifier],
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneMetadata {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scene {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub metadata: SceneMetadata,
+    /// The room or zone this scene belongs to.
+    pub group: ResourceIdentifier,
+    #[serde(default)]
+    pub actions: Vec<SceneAction>,
+    pub palette: Option<ScenePalette>,
+    /// Speed of the dynamic palette, between 0.0 and 1.0.
+    pub speed: Option<f32>,
+    /// Whether the scene starts in dynamic mode when recalled.
+    pub auto_dynamic: Option<bool>,
+    pub status: Option<SceneStatus>,
+}
+
+impl Scene {
+    /// Returns true if the scene is currently applied to its group, either statically or with a
+    /// dynamic palette.
+    pub fn is_active(&self) -> bool {
+        self.status
+            .as_ref()
+            .is_some_and(|status| status.active != SceneActiveState::Inactive)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SceneActiveState {
+    Inactive,
+    Static,
+    DynamicPalette,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneStatus {
+    pub active: SceneActiveState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SceneRecallAction {
+    /// Recalls the scene, starting its palette when `auto_dynamic` is set.
+    #[default]
+    Active,
+    /// Recalls the scene and starts cycling through its palette.
+    DynamicPalette,
+    /// Recalls the scene without starting its palette.
+    Static,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SceneRecall {
+    pub action: SceneRecallAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimming: Option<CommandLightDimming>,
+}
+
+impl SceneRecall {
+    pub fn with_action(self, action: SceneRecallAction) -> Self {
+        Self { action, ..self }
+    }
+
+    pub fn dynamic_palette(self) -> Self {
+        self.with_action(SceneRecallAction::DynamicPalette)
+    }
+
+    pub fn static_palette(self) -> Self {
+        self.with_action(SceneRecallAction::Static)
+    }
+
+    /// Transition time to the scene, in ms.
+    pub fn with_transition_time(self, ms: u32) -> Self {
+        Self {
+            duration: Some(ms),
+            ..self
+        }
+    }
+
+    /// Overrides the brightness of every light in the scene.
+    pub fn with_brightness(self, brightness: f32) -> Self {
+        Self {
+            dimming: Some(CommandLightDimming { brightness }),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandScene {
+    recall: SceneRecall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightDimming {
+    pub brightness: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightColorTemperature {
+    pub mirek: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightColor {
+    pub xy: XY,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeltaAction {
+    Up,
+    Down,
+    /// Stops an ongoing relative change.
+    Stop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightDimmingDelta {
+    pub action: DeltaAction,
+    /// Brightness percentage to add or remove, between 0.0 and 100.0.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brightness_delta: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightColorTemperatureDelta {
+    pub action: DeltaAction,
+    /// Mirek to add or remove, between 0 and 347.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirek_delta: Option<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightEffects {
+    pub effect: LightEffect,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightTimedEffects {
+    pub effect: TimedEffect,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightSignaling {
+    pub signal: Signal,
+    pub duration: u32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub colors: Vec<CommandLightColor>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandLightAlert {
+    pub action: AlertAction,
+}
+
+/// A multi-point gradient for lightstrips and other gradient capable lights.
+/// ### Example
+/// ```no_run
+/// let gradient = hueclient::CommandLightGradient::default()
+///     .with_mode(hueclient::GradientMode::InterpolatedPalette)
+///     .with_point(0.675, 0.322)
+///     .with_point(0.409, 0.518)
+///     .with_point(0.167, 0.04);
+/// let cmd = hueclient::CommandLight::default().on().with_gradient(gradient);
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommandLightGradient {
+    pub points: Vec<GradientPoint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mode: Option<GradientMode>,
+}
+
+impl CommandLightGradient {
+    pub fn with_point(mut self, x: f32, y: f32) -> Self {
+        self.points.push(GradientPoint {
+            color: CommandLightColor { xy: XY { x, y } },
+        });
+        self
+    }
+
+    pub fn with_mode(self, mode: GradientMode) -> Self {
+        Self {
+            mode: Some(mode),
+            ..self
+        }
+    }
+
+    /// Checks that `light` supports gradients, this number of points and this mode.
+    pub fn validate(&self, light: &Light) -> crate::Result<()> {
+        let capabilities = light.gradient.as_ref().ok_or_else(|| {
+            crate::HueError::invalid_command(format!("light {} has no gradient", light.id))
+        })?;
+        if self.points.len() < 2 || self.points.len() > capabilities.points_capable as usize {
+            return Err(crate::HueError::invalid_command(format!(
+                "light {} accepts 2 to {} gradient points, got {}",
+                light.id,
+                capabilities.points_capable,
+                self.points.len()
+            )));
+        }
+        if let Some(mode) = self.mode {
+            if !capabilities.mode_values.contains(&mode) {
+                return Err(crate::HueError::invalid_command(format!(
+                    "light {} does not support gradient mode {:?}",
+                    light.id, mode
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommandLightDynamics {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speed: Option<f32>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommandLight {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<On>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimming: Option<CommandLightDimming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_temperature: Option<CommandLightColorTemperature>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<CommandLightColor>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dynamics: Option<CommandLightDynamics>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effects: Option<CommandLightEffects>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timed_effects: Option<CommandLightTimedEffects>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signaling: Option<CommandLightSignaling>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alert: Option<CommandLightAlert>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gradient: Option<CommandLightGradient>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimming_delta: Option<CommandLightDimmingDelta>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color_temperature_delta: Option<CommandLightColorTemperatureDelta>,
+}
+
+impl CommandLight {
+    pub fn on(self) -> Self {
+        Self {
+            on: Some(On { on: true }),
+            ..self
+        }
+    }
+    pub fn off(self) -> Self {
+        Self {
+            on: Some(On { on: false }),
+            ..self
+        }
+    }
+
+    pub fn with_brightness(self, brightness: f32) -> Self {
+        Self {
+            dimming: Some(CommandLightDimming { brightness }),
+            ..self
+        }
+    }
+
+    pub fn with_mirek(self, mirek: u16) -> Self {
+        Self {
+            color_temperature: Some(CommandLightColorTemperature { mirek }),
+            ..self
+        }
+    }
+
+    /// Sets the color temperature, without checking whether the light accepts it.
+    /// ### Example
+    /// ```
+    /// use hueclient::color::Kelvin;
+    /// let cmd = hueclient::CommandLight::default().with_kelvin(Kelvin(2700));
+    /// ```
+    pub fn with_kelvin(self, kelvin: Kelvin) -> Self {
+        self.with_mirek(Mirek::from(kelvin).0)
+    }
+
+    /// Sets the color temperature, given as `Mirek` or `Kelvin`, moving it to the closest one the
+    /// light accepts. Fails if the light does not support color temperatures.
+    pub fn with_mirek_for(self, light: &Light, mirek: impl Into<Mirek>) -> crate::Result<Self> {
+        let schema = light.mirek_schema().ok_or_else(|| {
+            crate::HueError::invalid_command(format!(
+                "light {} does not support color temperatures",
+                light.id
+            ))
+        })?;
+        Ok(self.with_mirek(schema.clamp(mirek.into()).0))
+    }
+
+    /// Same as `with_kelvin`, but moves color temperatures the light does not accept to the
+    /// closest one it does. Fails if the light does not support color temperatures.
+    pub fn with_kelvin_for(self, light: &Light, kelvin: Kelvin) -> crate::Result<Self> {
+        self.with_mirek_for(light, kelvin)
+    }
+
+    pub fn with_xy(self, x: f32, y: f32) -> Self {
+        Self {
+            color: Some(CommandLightColor { xy: XY { x, y } }),
+            ..self
+        }
+    }
+
+    /// Sets the color and brightness from an 8-bit sRGB color.
+    pub fn with_rgb(self, red: u8, green: u8, blue: u8) -> Self {
+        self.with_color(crate::color::rgb_to_xy(red, green, blue), None)
+    }
+
+    /// Same as `with_rgb`, but moves colors the light cannot show to the closest one it can.
+    pub fn with_rgb_for(self, light: &Light, red: u8, green: u8, blue: u8) -> Self {
+        self.with_color(crate::color::rgb_to_xy(red, green, blue), light.gamut())
+    }
+
+    /// Sets the color and brightness from a color written as `RRGGBB` or `#RRGGBB`.
+    pub fn with_hex(self, hex: &str) -> crate::Result<Self> {
+        Ok(self.with_color(crate::color::hex_to_xy(hex)?, None))
+    }
+
+    /// Same as `with_hex`, but moves colors the light cannot show to the closest one it can.
+    pub fn with_hex_for(self, light: &Light, hex: &str) -> crate::Result<Self> {
+        Ok(self.with_color(crate::color::hex_to_xy(hex)?, light.gamut()))
+    }
+
+    fn with_color(self, color: crate::color::XyBrightness, gamut: Option<&Gamut>) -> Self {
+        let xy = match gamut {
+            Some(gamut) => crate::color::clamp_to_gamut(&color.xy, gamut),
+            None => color.xy,
+        };
+        self.with_xy(xy.x, xy.y).with_brightness(color.brightness)
+    }
+
+    pub fn with_transition_time(self, ms: u32) -> Self {
+        Self {
+            dynamics: Some(CommandLightDynamics {
+                duration: Some(ms),
+                ..Default::default()
+            }),
+            ..self
+        }
+    }
+
+    /// Changes the brightness relative to its current value, without reading it first.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let cmd = hueclient::CommandLight::default()
+    ///     .with_dimming_delta(hueclient::DeltaAction::Up, 10.0);
+    /// bridge.set_group_state("2b3b4e5e-67a9-4c7d-9a3b-5ed4a1f3c9b2", &cmd).await.unwrap();
+    /// # })
+    /// ```
+    pub fn with_dimming_delta(self, action: DeltaAction, brightness_delta: f32) -> Self {
+        Self {
+            dimming_delta: Some(CommandLightDimmingDelta {
+                action,
+                brightness_delta: Some(brightness_delta),
+            }),
+            ..self
+        }
+    }
+
+    /// Changes the color temperature relative to its current value. `DeltaAction::Up` makes the
+    /// light warmer, `DeltaAction::Down` cooler.
+    pub fn with_mirek_delta(self, action: DeltaAction, mirek_delta: u16) -> Self {
+        Self {
+            color_temperature_delta: Some(CommandLightColorTemperatureDelta {
+                action,
+                mirek_delta: Some(mirek_delta),
+            }),
+            ..self
+        }
+    }
+
+    pub fn brighter(self, brightness_delta: f32) -> Self {
+        self.with_dimming_delta(DeltaAction::Up, brightness_delta)
+    }
+
+    pub fn dimmer(self, brightness_delta: f32) -> Self {
+        self.with_dimming_delta(DeltaAction::Down, brightness_delta)
+    }
+
+    pub fn warmer(self, mirek_delta: u16) -> Self {
+        self.with_mirek_delta(DeltaAction::Up, mirek_delta)
+    }
+
+    pub fn cooler(self, mirek_delta: u16) -> Self {
+        self.with_mirek_delta(DeltaAction::Down, mirek_delta)
+    }
+
+    /// Starts a looping effect. Check `Light::supported_effects` for the ones a light accepts.
+    pub fn with_effect(self, effect: LightEffect) -> Self {
+        Self {
+            effects: Some(CommandLightEffects { effect }),
+            ..self
+        }
+    }
+
+    /// Starts an effect that runs once over `ms` milliseconds, such as a sunrise.
+    pub fn with_timed_effect(self, effect: TimedEffect, ms: u32) -> Self {
+        Self {
+            timed_effects: Some(CommandLightTimedEffects {
+                effect,
+                duration: Some(ms),
+            }),
+            ..self
+        }
+    }
+
+    /// Makes the light signal for `ms` milliseconds. `colors` is used by `Signal::OnOffColor`
+    /// (one color) and `Signal::Alternating` (two colors).
+    pub fn with_signal(self, signal: Signal, ms: u32, colors: &[XY]) -> Self {
+        Self {
+            signaling: Some(CommandLightSignaling {
+                signal,
+                duration: ms,
+                colors: colors
+                    .iter()
+                    .map(|xy| CommandLightColor { xy: xy.clone() })
+                    .collect(),
+            }),
+            ..self
+        }
+    }
+
+    pub fn with_gradient(self, gradient: CommandLightGradient) -> Self {
+        Self {
+            gradient: Some(gradient),
+            ..self
+        }
+    }
+
+    /// Same as `with_gradient`, but first validates the gradient against the capabilities of
+    /// the light it is meant for.
+    pub fn with_gradient_for(
+        self,
+        light: &Light,
+        gradient: CommandLightGradient,
+    ) -> crate::Result<Self> {
+        gradient.validate(light)?;
+        Ok(self.with_gradient(gradient))
+    }
+
+    /// Makes the light breathe once, typically to identify it.
+    pub fn with_alert(self, action: AlertAction) -> Self {
+        Self {
+            alert: Some(CommandLightAlert { action }),
+            ..self
+        }
+    }
+}
+
+/// Describes the powerup behavior to configure with `Bridge::set_light_powerup`.
+/// ### Example
+/// ```no_run
+/// let powerup = hueclient::CommandPowerup::new(hueclient::PowerupPreset::Custom)
+///     .with_on(true)
+///     .with_brightness(40.0)
+///     .with_mirek(366);
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResolvedRoom {
-    pub id: String,
-    pub id_v1: Option<String>,
-    pub metadata: Metadata,
-    pub children: Vec<Light>,
-    pub services: Vec<ResourceIdentifier>,
+pub struct CommandPowerup {
+    pub preset: PowerupPreset,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on: Option<PowerupOn>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimming: Option<PowerupDimming>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<PowerupColor>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Zone {
-    pub id: String,
-    pub id_v1: Option<String>,
-    pub metadata: Metadata,
-    pub children: Vec<ResourceIdentifier>,
-    pub services: Vec<ResourceIdentifier>,
-}
+impl CommandPowerup {
+    pub fn new(preset: PowerupPreset) -> Self {
+        Self {
+            preset,
+            on: None,
+            dimming: None,
+            color: None,
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResolvedZone {
-    pub id: String,
-    pub id_v1: Option<String>,
-    pub metadata: Metadata,
-    pub children: Vec<Light>,
-    pub services: Vec<ResourceIdentifier>,
-}
+    pub fn with_on(self, on: bool) -> Self {
+        Self {
+            on: Some(PowerupOn {
+                mode: PowerupOnMode::On,
+                on: Some(On { on }),
+            }),
+            ..self
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SceneMetadata {
-    pub name: String,
-}
+    /// Toggles the light each time it gets powered on.
+    pub fn with_toggle(self) -> Self {
+        Self {
+            on: Some(PowerupOn {
+                mode: PowerupOnMode::Toggle,
+                on: None,
+            }),
+            ..self
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Scene {
-    pub id: String,
-    pub id_v1: Option<String>,
-    pub metadata: SceneMetadata,
-}
+    pub fn with_brightness(self, brightness: f32) -> Self {
+        Self {
+            dimming: Some(PowerupDimming {
+                mode: PowerupDimmingMode::Dimming,
+                dimming: Some(CommandLightDimming { brightness }),
+            }),
+            ..self
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SceneRecall {
-    pub action: String,
-}
+    pub fn with_mirek(self, mirek: u16) -> Self {
+        Self {
+            color: Some(PowerupColor {
+                mode: PowerupColorMode::ColorTemperature,
+                color_temperature: Some(CommandLightColorTemperature { mirek }),
+                color: None,
+            }),
+            ..self
+        }
+    }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CommandScene {
-    recall: SceneRecall,
+    pub fn with_xy(self, x: f32, y: f32) -> Self {
+        Self {
+            color: Some(PowerupColor {
+                mode: PowerupColorMode::Color,
+                color_temperature: None,
+                color: Some(CommandLightColor { xy: XY { x, y } }),
+            }),
+            ..self
+        }
+    }
 }
 
+/// The action a scene applies to one of its target lights.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CommandLightDimming {
-    pub brightness: f32,
+pub struct SceneAction {
+    pub target: ResourceIdentifier,
+    pub action: CommandLight,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CommandLightColorTemperature {
-    pub mirek: u16,
+pub struct ScenePaletteColor {
+    pub color: CommandLightColor,
+    pub dimming: CommandLightDimming,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CommandLightColor {
-    pub xy: XY,
+pub struct ScenePaletteColorTemperature {
+    pub color_temperature: CommandLightColorTemperature,
+    pub dimming: CommandLightDimming,
 }
 
+/// The set of colors a dynamic scene cycles through.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct CommandLightDynamics {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    duration: Option<u32>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    speed: Option<f32>,
+pub struct ScenePalette {
+    #[serde(default)]
+    pub color: Vec<ScenePaletteColor>,
+    #[serde(default)]
+    pub dimming: Vec<CommandLightDimming>,
+    #[serde(default)]
+    pub color_temperature: Vec<ScenePaletteColorTemperature>,
 }
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct CommandLight {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub on: Option<On>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub dimming: Option<CommandLightDimming>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub color_temperature: Option<CommandLightColorTemperature>,
+
+/// Describes a scene to be created with `Bridge::create_scene`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandCreateScene {
+    pub metadata: SceneMetadata,
+    /// The room or zone this scene belongs to.
+    pub group: ResourceIdentifier,
+    pub actions: Vec<SceneAction>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub color: Option<CommandLightColor>,
+    pub palette: Option<ScenePalette>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub dynamics: Option<CommandLightDynamics>,
+    pub speed: Option<f32>,
 }
 
-impl CommandLight {
-    pub fn on(self) -> Self {
+impl CommandCreateScene {
+    pub fn new(name: impl Into<String>, group: ResourceIdentifier) -> Self {
         Self {
-            on: Some(On { on: true }),
-            ..self
+            metadata: SceneMetadata { name: name.into() },
+            group,
+            actions: vec![],
+            palette: None,
+            speed: None,
         }
     }
-    pub fn off(self) -> Self {
+
+    pub fn with_action(mut self, light: &str, action: CommandLight) -> Self {
+        self.actions.push(SceneAction {
+            target: ResourceIdentifier {
+                rid: light.to_string(),
+                rtype: "light".to_string(),
+            },
+            action,
+        });
+        self
+    }
+
+    pub fn with_palette(self, palette: ScenePalette) -> Self {
         Self {
-            on: Some(On { on: false }),
+            palette: Some(palette),
             ..self
         }
     }
 
-    pub fn with_brightness(self, brightness: f32) -> Self {
+    /// Speed of the dynamic palette, between 0.0 and 1.0.
+    pub fn with_speed(self, speed: f32) -> Self {
         Self {
-            dimming: Some(CommandLightDimming { brightness }),
+            speed: Some(speed),
             ..self
         }
     }
+}
 
-    pub fn with_mirek(self, mirek: u16) -> Self {
+/// Describes the changes to apply to an existing scene with `Bridge::update_scene`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommandUpdateScene {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<SceneMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actions: Option<Vec<SceneAction>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<ScenePalette>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<f32>,
+}
+
+impl CommandUpdateScene {
+    pub fn with_name(self, name: impl Into<String>) -> Self {
         Self {
-            color_temperature: Some(CommandLightColorTemperature { mirek }),
+            metadata: Some(SceneMetadata { name: name.into() }),
             ..self
         }
     }
 
-    pub fn with_xy(self, x: f32, y: f32) -> Self {
+    /// Replaces the actions of the scene. Lights not listed here are removed from the scene.
+    pub fn with_actions(self, actions: Vec<SceneAction>) -> Self {
         Self {
-            color: Some(CommandLightColor { xy: XY { x, y } }),
+            actions: Some(actions),
             ..self
         }
     }
 
-    pub fn with_transition_time(self, ms: u32) -> Self {
+    pub fn with_palette(self, palette: ScenePalette) -> Self {
         Self {
-            dynamics: Some(CommandLightDynamics {
-                duration: Some(ms),
-                ..Default::default()
-            }),
+            palette: Some(palette),
             ..self
         }
     }
-}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EventColorTemperature {
-    pub mirek: Option<u16>,
-    pub mirek_valid: bool,
+    /// Speed of the dynamic palette, between 0.0 and 1.0.
+    pub fn with_speed(self, speed: f32) -> Self {
+        Self {
+            speed: Some(speed),
+            ..self
+        }
+    }
 }
 
+/// The public configuration of a bridge, as returned by `UnauthBridge::probe`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Event {
-    pub id: String,
-    pub id_v1: Option<String>,
-    pub on: Option<On>,
-    pub dimming: Option<CommandLightDimming>,
-    pub color_temperature: Option<EventColorTemperature>,
-    pub color: Option<CommandLightColor>,
+pub struct BridgeInfo {
+    pub name: String,
+    /// The unique id of the bridge, such as `001788FFFE6A5B4C`.
+    pub bridgeid: String,
+    pub modelid: String,
+    /// The version of the v1 API.
+    pub apiversion: String,
+    pub swversion: String,
+    pub mac: String,
 }
 
 /// An unauthenticated bridge is a bridge that has not
@@ -260,10 +1515,55 @@ pub struct Event {
 pub struct UnauthBridge {
     /// The IP-address of the bridge.
     pub ip: std::net::IpAddr,
+    identity: BridgeIdentity,
     client: reqwest::Client,
 }
 
 impl UnauthBridge {
+    /// Consumes the bridge and returns a new one that only accepts a certificate issued for this
+    /// bridge id, such as `001788fffe6a5b4c`. The id is reported by `Bridge::discover_all`.
+    /// Without an id, any certificate signed by the Hue root CA is accepted.
+    /// ### Example
+    /// ```no_run
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///     .with_bridge_id("001788fffe6a5b4c")
+    ///     .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// ```
+    pub fn with_bridge_id(self, bridge_id: impl Into<String>) -> Self {
+        let identity = BridgeIdentity {
+            bridge_id: Some(bridge_id.into().to_lowercase()),
+            ..self.identity.clone()
+        };
+        self.with_identity(identity)
+    }
+
+    /// Consumes the bridge and returns a new one verifying the bridge certificate using `policy`.
+    /// ### Example
+    /// ```no_run
+    /// let pin = hueclient::CertificatePin::new();
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///     .with_certificate_policy(hueclient::CertificatePolicy::TrustOnFirstUse(pin.clone()))
+    ///     .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// // bridges with a certificate signed by the Hue root CA are never pinned, but for older
+    /// // bridges with a self-signed certificate, pin.fingerprint() is set by the first request
+    /// // and can be stored next to the application key
+    /// ```
+    pub fn with_certificate_policy(self, policy: CertificatePolicy) -> Self {
+        let identity = BridgeIdentity {
+            policy,
+            ..self.identity.clone()
+        };
+        self.with_identity(identity)
+    }
+
+    fn with_identity(self, identity: BridgeIdentity) -> Self {
+        Self {
+            client: create_reqwest_client(None, &identity),
+            identity,
+            ..self
+        }
+    }
+
     /// Consumes the bridge and returns a new one with a configured username.
     /// ### Example
     /// ```no_run
@@ -274,8 +1574,27 @@ impl UnauthBridge {
         let username = username.into();
         Bridge {
             ip: self.ip,
-            client: create_reqwest_client(Some(&username)),
+            client: create_reqwest_client(Some(&username), &self.identity),
             application_key: username,
+            client_key: None,
+            identity: self.identity,
+        }
+    }
+
+    /// Consumes the bridge and returns a new one using credentials stored from a previous
+    /// registration.
+    /// ### Example
+    /// ```no_run
+    /// let credentials = hueclient::Credentials {
+    ///     application_key: "rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj".into(),
+    ///     client_key: Some("321c0c2ebfa7361e55491095b2f5f9db".into()),
+    /// };
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4]).with_credentials(credentials);
+    /// ```
+    pub fn with_credentials(self, credentials: Credentials) -> Bridge {
+        Bridge {
+            client_key: credentials.client_key,
+            ..self.with_user(credentials.application_key)
         }
     }
 
@@ -292,72 +1611,148 @@ impl UnauthBridge {
     /// # })
     /// ```
     pub async fn register_application(self, name: &str) -> crate::Result<Bridge> {
-        #[derive(Serialize)]
-        struct PostApi {
-            devicetype: String,
-        }
-        #[derive(Debug, Deserialize)]
-        struct Username {
-            username: String,
+        let credentials = register(&self.client, self.ip, name, false).await?;
+        Ok(self.with_credentials(credentials))
+    }
+
+    /// Same as `register_application`, but the bridge also generates the client key needed for
+    /// entertainment streaming.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let mut bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4]);
+    /// let auth_bridge = bridge
+    ///     .register_application_with_client_key("mylaptop")
+    ///     .await
+    ///     .unwrap();
+    /// let credentials = auth_bridge.credentials();
+    /// // now these credentials can be stored and reused
+    /// # })
+    /// ```
+    pub async fn register_application_with_client_key(self, name: &str) -> crate::Result<Bridge> {
+        let credentials = register(&self.client, self.ip, name, true).await?;
+        Ok(self.with_credentials(credentials))
+    }
+
+    /// Registers a new application like `register_application_with_client_key`, retrying until
+    /// the button of the bridge is pressed or `timeout` expires. `on_waiting` is called with the
+    /// remaining time each time the bridge reports that the button was not pressed yet.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///     .register_application_with_retry(
+    ///         "mylaptop",
+    ///         std::time::Duration::from_secs(30),
+    ///         |remaining| println!("press the bridge button within {}s", remaining.as_secs()),
+    ///     )
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn register_application_with_retry(
+        self,
+        name: &str,
+        timeout: std::time::Duration,
+        mut on_waiting: impl FnMut(std::time::Duration),
+    ) -> crate::Result<Bridge> {
+        const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let attempt = self.clone().register_application_with_client_key(name);
+            match tokio::time::timeout_at(deadline, attempt).await {
+                Ok(Err(crate::HueError::BridgeError { code: 101, .. })) => {
+                    on_waiting(deadline.saturating_duration_since(tokio::time::Instant::now()))
+                }
+                Ok(result) => return result,
+                Err(_) => break,
+            }
+            let next = tokio::time::Instant::now() + POLL_INTERVAL;
+            if next >= deadline {
+                break;
+            }
+            tokio::time::sleep_until(next).await;
         }
-        let obtain = PostApi {
-            devicetype: name.to_string(),
-        };
-        let url = format!("https://{}/api", self.ip);
-        let resp: BridgeResponse<SuccessResponse<Username>> = self
-            .client
-            .post(&url)
-            .json(&obtain)
-            .send()
-            .await?
-            .json()
-            .await?;
-        let resp = resp.get()?;
+        Err(crate::HueError::RegistrationTimeout { timeout })
+    }
 
-        let username = resp.success.username;
-        Ok(Bridge {
-            ip: self.ip,
-            client: create_reqwest_client(Some(&username)),
-            application_key: username,
-        })
+    /// Fetches the public configuration of the bridge, which does not require an application key.
+    /// This confirms that a bridge is really present at the IP-address.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let info = hueclient::Bridge::for_ip([192u8, 168, 0, 4]).probe().await.unwrap();
+    /// println!("{} ({}) runs API version {}", info.name, info.bridgeid, info.apiversion);
+    /// # })
+    /// ```
+    pub async fn probe(&self) -> crate::Result<BridgeInfo> {
+        let url = format!("https://{}/api/0/config", self.ip);
+        Ok(self.client.get(&url).send().await?.json().await?)
     }
 }
 
 /// The bridge is the central access point of the lamps is a Hue setup, and also the central access
 /// point of this library.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Bridge {
     /// The IP-address of the bridge.
     pub ip: std::net::IpAddr,
     /// This is the username of the currently logged in user.
     pub application_key: String,
+    /// The key used to set up entertainment streaming, if it was generated during registration.
+    pub client_key: Option<String>,
+    identity: BridgeIdentity,
     client: reqwest::Client,
 }
 
-fn create_reqwest_client(application_key: Option<&str>) -> reqwest::Client {
+/// The keys handed out by the bridge when registering an application.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    /// The key authenticating every request, also known as the username.
+    pub application_key: String,
+    /// The pre-shared key for entertainment streaming, as a hex string. Only present when it was
+    /// requested during registration.
+    pub client_key: Option<String>,
+}
+
+async fn register(
+    client: &reqwest::Client,
+    ip: std::net::IpAddr,
+    name: &str,
+    generate_client_key: bool,
+) -> crate::Result<Credentials> {
+    #[derive(Serialize)]
+    struct PostApi {
+        devicetype: String,
+        #[serde(skip_serializing_if = "std::ops::Not::not")]
+        generateclientkey: bool,
+    }
+    #[derive(Debug, Deserialize)]
+    struct Username {
+        username: String,
+        clientkey: Option<String>,
+    }
+    let obtain = PostApi {
+        devicetype: name.to_string(),
+        generateclientkey: generate_client_key,
+    };
+    let url = format!("https://{}/api", ip);
+    let resp: BridgeResponse<SuccessResponse<Username>> =
+        client.post(&url).json(&obtain).send().await?.json().await?;
+    let resp = resp.get()?;
+
+    Ok(Credentials {
+        application_key: resp.success.username,
+        client_key: resp.success.clientkey,
+    })
+}
+
+fn create_reqwest_client(
+    application_key: Option<&str>,
+    identity: &BridgeIdentity,
+) -> reqwest::Client {
     reqwest::Client::builder()
-        // see https://developers.meethue.com/develop/application-design-guidance/using-https/
-        .add_root_certificate(
-            reqwest::Certificate::from_pem(
-                b"-----BEGIN CERTIFICATE-----
-MIICMjCCAdigAwIBAgIUO7FSLbaxikuXAljzVaurLXWmFw4wCgYIKoZIzj0EAwIw
-OTELMAkGA1UEBhMCTkwxFDASBgNVBAoMC1BoaWxpcHMgSHVlMRQwEgYDVQQDDAty
-b290LWJyaWRnZTAiGA8yMDE3MDEwMTAwMDAwMFoYDzIwMzgwMTE5MDMxNDA3WjA5
-MQswCQYDVQQGEwJOTDEUMBIGA1UECgwLUGhpbGlwcyBIdWUxFDASBgNVBAMMC3Jv
-b3QtYnJpZGdlMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEjNw2tx2AplOf9x86
-aTdvEcL1FU65QDxziKvBpW9XXSIcibAeQiKxegpq8Exbr9v6LBnYbna2VcaK0G22
-jOKkTqOBuTCBtjAPBgNVHRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwIBhjAdBgNV
-HQ4EFgQUZ2ONTFrDT6o8ItRnKfqWKnHFGmQwdAYDVR0jBG0wa4AUZ2ONTFrDT6o8
-ItRnKfqWKnHFGmShPaQ7MDkxCzAJBgNVBAYTAk5MMRQwEgYDVQQKDAtQaGlsaXBz
-IEh1ZTEUMBIGA1UEAwwLcm9vdC1icmlkZ2WCFDuxUi22sYpLlwJY81Wrqy11phcO
-MAoGCCqGSM49BAMCA0gAMEUCIEBYYEOsa07TH7E5MJnGw557lVkORgit2Rm1h3B2
-sFgDAiEA1Fj/C3AN5psFMjo0//mrQebo0eKd3aWRx+pQY08mk48=
------END CERTIFICATE-----",
-            )
-            .expect("using rustls and this hardcoded certificate should never fail"),
-        )
-        // TODO properly handle older bridges that still use a self-signed certificate
-        .danger_accept_invalid_certs(true)
+        .use_preconfigured_tls(crate::tls::client_config(identity))
         .default_headers({
             let mut headers = reqwest::header::HeaderMap::new();
             if let Some(key) = application_key {
@@ -373,6 +1768,16 @@ sFgDAiEA1Fj/C3AN5psFMjo0//mrQebo0eKd3aWRx+pQY08mk48=
         .unwrap()
 }
 
+impl From<DiscoveredBridge> for UnauthBridge {
+    fn from(found: DiscoveredBridge) -> Self {
+        let bridge = Bridge::for_ip(found.ip);
+        match found.id {
+            Some(id) => bridge.with_bridge_id(id),
+            None => bridge,
+        }
+    }
+}
+
 impl Bridge {
     /// Create a bridge at this IP. If you know the IP-address, this is the fastest option. Note
     /// that this function does not validate whether a bridge is really present at the IP-address.
@@ -381,26 +1786,45 @@ impl Bridge {
     /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4]);
     /// ```
     pub fn for_ip(ip: impl Into<std::net::IpAddr>) -> UnauthBridge {
+        let identity = BridgeIdentity::default();
         UnauthBridge {
             ip: ip.into(),
-            client: create_reqwest_client(None),
+            client: create_reqwest_client(None, &identity),
+            identity,
         }
     }
 
     /// Scans the current network for Bridges, and if there is at least one, returns the first one
-    /// that was found.
+    /// that was found. When the discovery method reported the id of the bridge, only its
+    /// certificate is accepted.
     /// ### Example
     /// ```no_run
-    /// let maybe_bridge = hueclient::Bridge::discover();
+    /// # tokio_test::block_on(async {
+    /// let options = hueclient::DiscoveryOptions::default();
+    /// let maybe_bridge = hueclient::Bridge::discover(options).await;
+    /// # })
     /// ```
-    pub async fn discover() -> Option<UnauthBridge> {
-        crate::disco::discover_hue_bridge()
+    pub async fn discover(options: DiscoveryOptions) -> Option<UnauthBridge> {
+        crate::disco::discover_hue_bridge(&options)
             .await
             .ok()
-            .map(|ip| UnauthBridge {
-                ip,
-                client: create_reqwest_client(None),
-            })
+            .map(UnauthBridge::from)
+    }
+
+    /// Scans the current network for Bridges using both mDNS and the Philips discovery
+    /// endpoint, and returns every bridge that answered before `options.timeout` expired.
+    /// Bridges found by both methods are only reported once.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let options = hueclient::DiscoveryOptions::default();
+    /// for found in hueclient::Bridge::discover_all(options).await.unwrap() {
+    ///     println!("{:?} at {}:{}", found.id, found.ip, found.port);
+    /// }
+    /// # })
+    /// ```
+    pub async fn discover_all(options: DiscoveryOptions) -> crate::Result<Vec<DiscoveredBridge>> {
+        crate::disco::discover_all_hue_bridges(&options).await
     }
 
     /// A convience wrapper around `Bridge::disover`, but panics if there is no bridge present.
@@ -411,7 +1835,9 @@ impl Bridge {
     /// ### Panics
     /// This function panics if there is no brige present.
     pub async fn discover_required() -> UnauthBridge {
-        Self::discover().await.expect("No bridge found!")
+        Self::discover(DiscoveryOptions::default())
+            .await
+            .expect("No bridge found!")
     }
 
     /// Consumes the bidge and return a new one with a configured username.
@@ -424,6 +1850,8 @@ impl Bridge {
         Bridge {
             ip: self.ip,
             application_key: appplication_key.into(),
+            client_key: self.client_key,
+            identity: self.identity,
             client: self.client,
         }
     }
@@ -443,33 +1871,46 @@ impl Bridge {
     /// # })
     /// ```
     pub async fn register_application(self, name: &str) -> crate::Result<Bridge> {
-        #[derive(Serialize)]
-        struct PostApi {
-            devicetype: String,
-        }
-        #[derive(Debug, Deserialize)]
-        struct Username {
-            username: String,
+        let credentials = register(&self.client, self.ip, name, false).await?;
+        Ok(self.with_registered(credentials))
+    }
+
+    /// Same as `register_application`, but the bridge also generates the client key needed for
+    /// entertainment streaming.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///     .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj")
+    ///     .register_application_with_client_key("mylaptop")
+    ///     .await
+    ///     .unwrap();
+    /// // now these credentials can be stored and reused
+    /// println!("the password was {}", bridge.application_key);
+    /// println!("the client key was {:?}", bridge.client_key);
+    /// # })
+    /// ```
+    pub async fn register_application_with_client_key(self, name: &str) -> crate::Result<Bridge> {
+        let credentials = register(&self.client, self.ip, name, true).await?;
+        Ok(self.with_registered(credentials))
+    }
+
+    fn with_registered(self, credentials: Credentials) -> Bridge {
+        Bridge {
+            client: create_reqwest_client(Some(&credentials.application_key), &self.identity),
+            application_key: credentials.application_key,
+            client_key: credentials.client_key,
+            ..self
         }
-        let obtain = PostApi {
-            devicetype: name.to_string(),
-        };
-        let url = format!("https://{}/api", self.ip);
-        let resp: BridgeResponse<SuccessResponse<Username>> = self
-            .client
-            .post(&url)
-            .json(&obtain)
-            .send()
-            .await?
-            .json()
-            .await?;
-        let resp = resp.get()?;
+    }
 
-        Ok(Bridge {
-            ip: self.ip,
-            application_key: resp.success.username,
-            client: self.client,
-        })
+    /// Returns the credentials of this bridge, to be stored and passed to
+    /// `UnauthBridge::with_credentials` later on.
+    pub fn credentials(&self) -> Credentials {
+        Credentials {
+            application_key: self.application_key.clone(),
+            client_key: self.client_key.clone(),
+        }
     }
 
     /// Returns a vector of all devices that are registered at this `Bridge`, sorted by their id's.
@@ -622,6 +2063,190 @@ impl Bridge {
             .collect())
     }
 
+    pub async fn get_room(&self, room: &str) -> crate::Result<Room> {
+        self.get_resource("room", room).await
+    }
+
+    /// Creates a new room holding the given devices and returns the identifier the bridge
+    /// assigned to it.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let metadata = hueclient::Metadata {
+    ///     name: "Kitchen".to_string(),
+    ///     archetype: "kitchen".to_string(),
+    /// };
+    /// let room = bridge.create_room(&metadata, &[]).await.unwrap();
+    /// # })
+    /// ```
+    pub async fn create_room(
+        &self,
+        metadata: &Metadata,
+        children: &[ResourceIdentifier],
+    ) -> crate::Result<ResourceIdentifier> {
+        self.create_resource("room", &CreateGroup { metadata, children })
+            .await
+    }
+
+    pub async fn update_room(&self, room: &str, command: &CommandGroup) -> crate::Result<()> {
+        self.update_resource("room", room, command).await
+    }
+
+    /// Adds devices to a room, leaving the ones it already contains in place.
+    pub async fn add_room_children(
+        &self,
+        room: &str,
+        children: &[ResourceIdentifier],
+    ) -> crate::Result<()> {
+        let current = self.get_room(room).await?.children;
+        let command = CommandGroup::default().with_children(add_children(current, children));
+        self.update_room(room, &command).await
+    }
+
+    pub async fn remove_room_children(
+        &self,
+        room: &str,
+        children: &[ResourceIdentifier],
+    ) -> crate::Result<()> {
+        let current = self.get_room(room).await?.children;
+        let command = CommandGroup::default().with_children(remove_children(current, children));
+        self.update_room(room, &command).await
+    }
+
+    pub async fn delete_room(&self, room: &str) -> crate::Result<()> {
+        self.delete_resource("room", room).await
+    }
+
+    pub async fn get_zone(&self, zone: &str) -> crate::Result<Zone> {
+        self.get_resource("zone", zone).await
+    }
+
+    /// Creates a new zone holding the given lights and returns the identifier the bridge
+    /// assigned to it.
+    pub async fn create_zone(
+        &self,
+        metadata: &Metadata,
+        children: &[ResourceIdentifier],
+    ) -> crate::Result<ResourceIdentifier> {
+        self.create_resource("zone", &CreateGroup { metadata, children })
+            .await
+    }
+
+    pub async fn update_zone(&self, zone: &str, command: &CommandGroup) -> crate::Result<()> {
+        self.update_resource("zone", zone, command).await
+    }
+
+    /// Adds lights to a zone, leaving the ones it already contains in place.
+    pub async fn add_zone_children(
+        &self,
+        zone: &str,
+        children: &[ResourceIdentifier],
+    ) -> crate::Result<()> {
+        let current = self.get_zone(zone).await?.children;
+        let command = CommandGroup::default().with_children(add_children(current, children));
+        self.update_zone(zone, &command).await
+    }
+
+    pub async fn remove_zone_children(
+        &self,
+        zone: &str,
+        children: &[ResourceIdentifier],
+    ) -> crate::Result<()> {
+        let current = self.get_zone(zone).await?.children;
+        let command = CommandGroup::default().with_children(remove_children(current, children));
+        self.update_zone(zone, &command).await
+    }
+
+    pub async fn delete_zone(&self, zone: &str) -> crate::Result<()> {
+        self.delete_resource("zone", zone).await
+    }
+
+    /// Returns a vector of all motion sensors that are registered at this `Bridge`, sorted by
+    /// their id's.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// for motion in &bridge.get_all_motion_sensors().await.unwrap() {
+    ///     println!("{} {:?}", motion.owner.rid, motion.motion.motion_report);
+    /// }
+    /// # })
+    /// ```
+    pub async fn get_all_motion_sensors(&self) -> crate::Result<Vec<Motion>> {
+        let mut sensors: Vec<Motion> = self.get_resources("motion").await?;
+        sensors.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(sensors)
+    }
+
+    pub async fn get_motion_sensor(&self, id: &str) -> crate::Result<Motion> {
+        self.get_resource("motion", id).await
+    }
+
+    /// Returns a vector of all temperature sensors that are registered at this `Bridge`, sorted
+    /// by their id's.
+    pub async fn get_all_temperature_sensors(&self) -> crate::Result<Vec<Temperature>> {
+        let mut sensors: Vec<Temperature> = self.get_resources("temperature").await?;
+        sensors.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(sensors)
+    }
+
+    pub async fn get_temperature_sensor(&self, id: &str) -> crate::Result<Temperature> {
+        self.get_resource("temperature", id).await
+    }
+
+    /// Returns a vector of all light level sensors that are registered at this `Bridge`, sorted
+    /// by their id's.
+    pub async fn get_all_light_level_sensors(&self) -> crate::Result<Vec<LightLevel>> {
+        let mut sensors: Vec<LightLevel> = self.get_resources("light_level").await?;
+        sensors.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(sensors)
+    }
+
+    pub async fn get_light_level_sensor(&self, id: &str) -> crate::Result<LightLevel> {
+        self.get_resource("light_level", id).await
+    }
+
+    /// Returns a vector of all buttons that are registered at this `Bridge`, sorted by their id's.
+    /// Dimmer switches, wall switch modules and the Tap Dial each expose one button per control.
+    pub async fn get_all_buttons(&self) -> crate::Result<Vec<Button>> {
+        let mut buttons: Vec<Button> = self.get_resources("button").await?;
+        buttons.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(buttons)
+    }
+
+    pub async fn get_button(&self, id: &str) -> crate::Result<Button> {
+        self.get_resource("button", id).await
+    }
+
+    /// Returns a vector of all relative rotaries (such as the Tap Dial ring) that are registered
+    /// at this `Bridge`, sorted by their id's.
+    pub async fn get_all_relative_rotaries(&self) -> crate::Result<Vec<RelativeRotary>> {
+        let mut rotaries: Vec<RelativeRotary> = self.get_resources("relative_rotary").await?;
+        rotaries.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(rotaries)
+    }
+
+    pub async fn get_relative_rotary(&self, id: &str) -> crate::Result<RelativeRotary> {
+        self.get_resource("relative_rotary", id).await
+    }
+
+    async fn get_resources<T: DeserializeOwned>(&self, rtype: &str) -> crate::Result<Vec<T>> {
+        let url = format!("https://{}/clip/v2/resource/{}", self.ip, rtype);
+        let resp: BridgeResponseV2<T> = self.client.get(&url).send().await?.json().await?;
+        resp.get()
+    }
+
+    async fn get_resource<T: DeserializeOwned>(&self, rtype: &str, id: &str) -> crate::Result<T> {
+        let url = format!("https://{}/clip/v2/resource/{}/{}", self.ip, rtype, id);
+        let resp: BridgeResponseV2<T> = self.client.get(&url).send().await?.json().await?;
+        resp.get()?
+            .pop()
+            .ok_or_else(|| crate::HueError::protocol_err("expected non-empty array"))
+    }
+
     /// Returns a vector of all scenes that are registered at this `Bridge`, sorted by their id's.
     /// This function returns an error if `bridge.username` is `None`.
     /// ### Example
@@ -642,15 +2267,53 @@ impl Bridge {
         Ok(scenes)
     }
 
+    /// Returns the scenes belonging to a room or zone, sorted by their id's. `group` is the id of
+    /// a `Room`, `Zone`, `ResolvedRoom` or `ResolvedZone`.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// for room in &bridge.resolve_all_rooms().await.unwrap() {
+    ///     for scene in &bridge.get_scenes_for_group(&room.id).await.unwrap() {
+    ///         println!("{} {} {}", room.metadata.name, scene.metadata.name, scene.is_active());
+    ///     }
+    /// }
+    /// # })
+    /// ```
+    pub async fn get_scenes_for_group(&self, group: &str) -> crate::Result<Vec<Scene>> {
+        let mut scenes = self.get_all_scenes().await?;
+        scenes.retain(|scene| scene.group.rid == group);
+        Ok(scenes)
+    }
+
     pub async fn set_scene(&self, scene: String) -> crate::Result<()> {
+        self.recall_scene(&scene, &SceneRecall::default()).await
+    }
+
+    /// Recalls a scene with a specific action, transition time or brightness.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let wake_up = hueclient::SceneRecall::default()
+    ///     .static_palette()
+    ///     .with_transition_time(30 * 60 * 1000)
+    ///     .with_brightness(80.0);
+    /// bridge
+    ///     .recall_scene("4a2ee9cd-0b5c-4a8f-9eab-e3ef1c6e3b91", &wake_up)
+    ///     .await
+    ///     .unwrap();
+    /// # })
+    /// ```
+    pub async fn recall_scene(&self, scene: &str, recall: &SceneRecall) -> crate::Result<()> {
         let url = format!("https://{}/clip/v2/resource/scene/{}", self.ip, scene);
         let resp: BridgeResponseV2<Value> = self
             .client
             .put(&url)
             .json(&CommandScene {
-                recall: SceneRecall {
-                    action: "active".to_string(),
-                },
+                recall: recall.clone(),
             })
             .send()
             .await?
@@ -661,6 +2324,278 @@ impl Bridge {
         Ok(())
     }
 
+    /// Creates a new scene and returns the identifier the bridge assigned to it.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let room = hueclient::ResourceIdentifier {
+    ///     rid: "b9bd6f36-5c24-4bd7-9e5b-d1d84f0b3b45".to_string(),
+    ///     rtype: "room".to_string(),
+    /// };
+    /// let scene = hueclient::CommandCreateScene::new("Reading", room).with_action(
+    ///     "6e9f8b1d-bd5b-44ff-a3a6-1e1d0a4e0d45",
+    ///     hueclient::CommandLight::default().on().with_mirek(233),
+    /// );
+    /// let id = bridge.create_scene(&scene).await.unwrap();
+    /// # })
+    /// ```
+    pub async fn create_scene(
+        &self,
+        command: &CommandCreateScene,
+    ) -> crate::Result<ResourceIdentifier> {
+        self.create_resource("scene", command).await
+    }
+
+    pub async fn update_scene(
+        &self,
+        scene: &str,
+        command: &CommandUpdateScene,
+    ) -> crate::Result<()> {
+        self.update_resource("scene", scene, command).await
+    }
+
+    pub async fn delete_scene(&self, scene: &str) -> crate::Result<()> {
+        self.delete_resource("scene", scene).await
+    }
+
+    async fn create_resource<C: Serialize>(
+        &self,
+        rtype: &str,
+        command: &C,
+    ) -> crate::Result<ResourceIdentifier> {
+        let url = format!("https://{}/clip/v2/resource/{}", self.ip, rtype);
+        let resp: BridgeResponseV2<ResourceIdentifier> = self
+            .client
+            .post(&url)
+            .json(command)
+            .send()
+            .await?
+            .json()
+            .await?;
+        resp.get()?
+            .pop()
+            .ok_or_else(|| crate::HueError::protocol_err("expected non-empty array"))
+    }
+
+    async fn update_resource<C: Serialize>(
+        &self,
+        rtype: &str,
+        id: &str,
+        command: &C,
+    ) -> crate::Result<()> {
+        let url = format!("https://{}/clip/v2/resource/{}/{}", self.ip, rtype, id);
+        let resp: BridgeResponseV2<Value> = self
+            .client
+            .put(&url)
+            .json(command)
+            .send()
+            .await?
+            .json()
+            .await?;
+        resp.get()?;
+        Ok(())
+    }
+
+    async fn delete_resource(&self, rtype: &str, id: &str) -> crate::Result<()> {
+        let url = format!("https://{}/clip/v2/resource/{}/{}", self.ip, rtype, id);
+        let resp: BridgeResponseV2<Value> = self.client.delete(&url).send().await?.json().await?;
+        resp.get()?;
+        Ok(())
+    }
+
+    /// Returns a vector of all entertainment configurations that are registered at this `Bridge`,
+    /// sorted by their id's.
+    pub async fn get_all_entertainment_configurations(
+        &self,
+    ) -> crate::Result<Vec<EntertainmentConfiguration>> {
+        let mut configurations: Vec<EntertainmentConfiguration> =
+            self.get_resources("entertainment_configuration").await?;
+        configurations.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(configurations)
+    }
+
+    pub async fn get_entertainment_configuration(
+        &self,
+        configuration: &str,
+    ) -> crate::Result<EntertainmentConfiguration> {
+        self.get_resource("entertainment_configuration", configuration)
+            .await
+    }
+
+    /// Creates a new entertainment configuration and returns the identifier the bridge assigned to
+    /// it. The bridge computes its channels from the locations.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use hueclient::{EntertainmentConfigurationType, Position, ServiceLocation};
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///     .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let configuration = hueclient::CommandCreateEntertainmentConfiguration::new(
+    ///     "Television",
+    ///     EntertainmentConfigurationType::Screen,
+    /// )
+    /// .with_location(ServiceLocation::new(
+    ///     "0f5ac4a1-0b9c-4b9f-b7c3-4d7a8a0b1f2e",
+    ///     vec![Position { x: -0.5, y: 0.8, z: 0.0 }],
+    /// ));
+    /// let id = bridge.create_entertainment_configuration(&configuration).await.unwrap();
+    /// # })
+    /// ```
+    pub async fn create_entertainment_configuration(
+        &self,
+        command: &CommandCreateEntertainmentConfiguration,
+    ) -> crate::Result<ResourceIdentifier> {
+        self.create_resource("entertainment_configuration", command)
+            .await
+    }
+
+    pub async fn update_entertainment_configuration(
+        &self,
+        configuration: &str,
+        command: &CommandUpdateEntertainmentConfiguration,
+    ) -> crate::Result<()> {
+        self.update_resource("entertainment_configuration", configuration, command)
+            .await
+    }
+
+    pub async fn delete_entertainment_configuration(
+        &self,
+        configuration: &str,
+    ) -> crate::Result<()> {
+        self.delete_resource("entertainment_configuration", configuration)
+            .await
+    }
+
+    /// Returns a vector of all entertainment services that are registered at this `Bridge`,
+    /// sorted by their id's.
+    pub async fn get_all_entertainment_services(&self) -> crate::Result<Vec<EntertainmentService>> {
+        let mut services: Vec<EntertainmentService> = self.get_resources("entertainment").await?;
+        services.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(services)
+    }
+
+    pub async fn get_entertainment_service(
+        &self,
+        service: &str,
+    ) -> crate::Result<EntertainmentService> {
+        self.get_resource("entertainment", service).await
+    }
+
+    /// Splits a gradient light into segments, each driven by its own channel in the
+    /// entertainment configurations holding the light. Only possible when
+    /// `EntertainmentSegments::configurable` is set.
+    pub async fn set_entertainment_segments(
+        &self,
+        service: &str,
+        segments: &[EntertainmentSegment],
+    ) -> crate::Result<()> {
+        #[derive(Serialize)]
+        struct PutSegments<'a> {
+            segments: SegmentList<'a>,
+        }
+        #[derive(Serialize)]
+        struct SegmentList<'a> {
+            segments: &'a [EntertainmentSegment],
+        }
+        let command = PutSegments {
+            segments: SegmentList { segments },
+        };
+        self.update_resource("entertainment", service, &command)
+            .await
+    }
+
+    /// Hands the lights of the entertainment configuration over to this application, which can
+    /// then stream to them.
+    pub async fn start_entertainment(&self, configuration: &str) -> crate::Result<()> {
+        self.set_entertainment_action(configuration, "start").await
+    }
+
+    /// Gives the lights of the entertainment configuration back to the bridge.
+    pub async fn stop_entertainment(&self, configuration: &str) -> crate::Result<()> {
+        self.set_entertainment_action(configuration, "stop").await
+    }
+
+    async fn set_entertainment_action(
+        &self,
+        configuration: &str,
+        action: &str,
+    ) -> crate::Result<()> {
+        #[derive(Serialize)]
+        struct PutAction<'a> {
+            action: &'a str,
+        }
+        self.update_resource(
+            "entertainment_configuration",
+            configuration,
+            &PutAction { action },
+        )
+        .await
+    }
+
+    /// Starts the entertainment configuration and opens a DTLS session to stream colors to it,
+    /// sending at most `frames_per_second` frames per second. The bridge forwards 25 frames per
+    /// second to the lights and recommends sending 50 to 60 to make up for lost packets. This
+    /// needs the client key generated during registration, and the `entertainment` feature.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4]).with_credentials(hueclient::Credentials {
+    ///     application_key: "rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj".into(),
+    ///     client_key: Some("321c0c2ebfa7361e55491095b2f5f9db".into()),
+    /// });
+    /// let configuration = "1a8d99cc-967b-44f2-9202-43f976c0fa6b";
+    /// let mut stream = bridge.open_entertainment_stream(configuration, 50).await.unwrap();
+    /// for i in 0..500 {
+    ///     let red = (i % 50) as f64 / 50.0;
+    ///     let frame = hueclient::EntertainmentFrame::Rgb(vec![(0, [red, 0.0, 0.0])]);
+    ///     stream.send(&frame).await.unwrap();
+    /// }
+    /// stream.close().await.unwrap();
+    /// # })
+    /// ```
+    #[cfg(feature = "entertainment")]
+    pub async fn open_entertainment_stream(
+        &self,
+        configuration: &str,
+        frames_per_second: u32,
+    ) -> crate::Result<crate::EntertainmentStream> {
+        const STREAMING_PORT: u16 = 2100;
+        if self.client_key.is_none() {
+            Err(crate::HueError::invalid_command(
+                "streaming needs the client key generated during registration",
+            ))?
+        }
+        self.start_entertainment(configuration).await?;
+        let stream = crate::EntertainmentStream::connect(
+            self.clone(),
+            (self.ip, STREAMING_PORT).into(),
+            configuration,
+            frames_per_second,
+        )
+        .await;
+        if stream.is_err() {
+            // release the configuration, so other applications can stream to it
+            if let Err(e) = self.stop_entertainment(configuration).await {
+                log::warn!("failed to stop entertainment configuration {configuration}: {e}");
+            }
+        }
+        stream
+    }
+
+    /// Returns a vector of all grouped lights that are registered at this `Bridge`, sorted by
+    /// their id's. There is one grouped light per room and zone, plus one for the whole home.
+    pub async fn get_all_grouped_lights(&self) -> crate::Result<Vec<GroupedLight>> {
+        let mut groups: Vec<GroupedLight> = self.get_resources("grouped_light").await?;
+        groups.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(groups)
+    }
+
+    pub async fn get_grouped_light(&self, group: &str) -> crate::Result<GroupedLight> {
+        self.get_resource("grouped_light", group).await
+    }
+
     pub async fn set_group_state(&self, group: &str, command: &CommandLight) -> crate::Result<()> {
         let url = format!(
             "https://{}/clip/v2/resource/grouped_light/{}",
@@ -692,41 +2627,227 @@ impl Bridge {
         Ok(())
     }
 
+    /// Configures what a light does when it gets powered on, for instance after a power outage.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let powerup = hueclient::CommandPowerup::new(hueclient::PowerupPreset::Powerfail);
+    /// for light in &bridge.get_all_lights().await.unwrap() {
+    ///     bridge.set_light_powerup(&light.id, &powerup).await.unwrap();
+    /// }
+    /// # })
+    /// ```
+    pub async fn set_light_powerup(
+        &self,
+        light: &str,
+        powerup: &CommandPowerup,
+    ) -> crate::Result<()> {
+        #[derive(Serialize)]
+        struct PutPowerup<'a> {
+            powerup: &'a CommandPowerup,
+        }
+        self.update_resource("light", light, &PutPowerup { powerup })
+            .await
+    }
+
+    /// Returns a stream of the changes happening on the bridge, one `HueEvent::Event` per
+    /// envelope sent by the bridge. The stream reconnects with the default
+    /// `EventStreamOptions` when the connection is lost.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use futures::StreamExt;
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let mut events = Box::pin(bridge.events().unwrap());
+    /// while let Some(hueclient::HueEvent::Event(envelope)) = events.next().await {
+    ///     for data in &envelope.data {
+    ///         if let hueclient::EventData::Motion(motion) = data {
+    ///             println!("{:?} {:?}", envelope.kind, motion.motion);
+    ///         }
+    ///     }
+    /// }
+    /// # })
+    /// ```
     pub fn events(&self) -> crate::Result<impl Stream<Item = HueEvent>> {
+        self.events_with_options(EventStreamOptions::default())
+    }
+
+    /// Same as `events`, with control over the reconnection backoff. Each reconnection is
+    /// signaled by a `HueEvent::Reconnected`, followed by a `HueEvent::Resync` if requested. The
+    /// stream only ends when the bridge rejects the application key.
+    /// ### Example
+    /// ```no_run
+    /// # tokio_test::block_on(async {
+    /// use futures::StreamExt;
+    /// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+    ///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+    /// let options = hueclient::EventStreamOptions::default().with_resync();
+    /// let mut events = Box::pin(bridge.events_with_options(options).unwrap());
+    /// while let Some(event) = events.next().await {
+    ///     if let hueclient::HueEvent::Resync(state) = event {
+    ///         println!("{} lights after reconnection", state.lights.len());
+    ///     }
+    /// }
+    /// # })
+    /// ```
+    pub fn events_with_options(
+        &self,
+        options: EventStreamOptions,
+    ) -> crate::Result<impl Stream<Item = HueEvent>> {
+        self.event_stream(options, false)
+    }
+
+    /// Same as `events_with_options`, but when `report_open` is set the first connection is
+    /// signaled by a `HueEvent::Reconnected` too, for callers waiting for the stream to be
+    /// connected before fetching state.
+    pub(crate) fn event_stream(
+        &self,
+        options: EventStreamOptions,
+        report_open: bool,
+    ) -> crate::Result<impl Stream<Item = HueEvent>> {
+        let source = self.event_source(&options)?;
+        let state = EventSourceLoop {
+            bridge: self.clone(),
+            delay: options.initial_delay,
+            options,
+            source: Some(source),
+            opened: false,
+            report_open,
+            done: false,
+        };
+        Ok(futures::stream::unfold(state, |mut state| async move {
+            state.next_events().await.map(|events| (events, state))
+        })
+        .flat_map(futures::stream::iter))
+    }
+
+    fn event_source(
+        &self,
+        options: &EventStreamOptions,
+    ) -> crate::Result<reqwest_eventsource::EventSource> {
         let request_builder = self.client.request(
             Method::GET,
             format!("https://{}/eventstream/clip/v2", self.ip),
         );
-        Ok(
-            reqwest_eventsource::EventSource::new(request_builder)?.filter_map(|event| async {
-                log::debug!("event {:?}", event);
-                match event {
-                    Ok(reqwest_eventsource::Event::Message(msg)) => {
-                        log::debug!("message {:?}", msg.data);
-                        match serde_json::from_str::<Vec<EventEnvelope>>(&msg.data) {
-                            Ok(mut event) => Some(HueEvent::Event {
-                                data: event.pop().unwrap().data,
-                            }),
-                            Err(e) => Some(HueEvent::Error(format!("{:?}", e))),
+        let mut source = reqwest_eventsource::EventSource::new(request_builder)?;
+        source.set_retry_policy(Box::new(
+            reqwest_eventsource::retry::ExponentialBackoff::new(
+                options.initial_delay,
+                options.factor,
+                Some(options.max_delay),
+                None,
+            ),
+        ));
+        Ok(source)
+    }
+
+    async fn resync(&self) -> HueEvent {
+        let lights = match self.get_all_lights().await {
+            Ok(lights) => lights,
+            Err(e) => return HueEvent::Error(format!("{:?}", e)),
+        };
+        match self.get_all_grouped_lights().await {
+            Ok(grouped_lights) => HueEvent::Resync(Resync {
+                lights,
+                grouped_lights,
+            }),
+            Err(e) => HueEvent::Error(format!("{:?}", e)),
+        }
+    }
+}
+
+// Reconnects to the event stream when it ends. `EventSource` retries lost connections by itself,
+// but closes for good when the bridge answers with an error status, such as while rebooting.
+struct EventSourceLoop {
+    bridge: Bridge,
+    options: EventStreamOptions,
+    source: Option<reqwest_eventsource::EventSource>,
+    delay: std::time::Duration,
+    opened: bool,
+    report_open: bool,
+    done: bool,
+}
+
+impl EventSourceLoop {
+    async fn next_events(&mut self) -> Option<Vec<HueEvent>> {
+        use reqwest_eventsource::{Error, Event};
+        while !self.done {
+            let Some(source) = &mut self.source else {
+                tokio::time::sleep(self.delay).await;
+                self.delay = self
+                    .delay
+                    .mul_f64(self.options.factor)
+                    .min(self.options.max_delay);
+                match self.bridge.event_source(&self.options) {
+                    Ok(source) => self.source = Some(source),
+                    Err(e) => return Some(vec![HueEvent::Error(format!("{:?}", e))]),
+                }
+                continue;
+            };
+            let event = source.next().await;
+            log::debug!("event {:?}", event);
+            match event {
+                Some(Ok(Event::Message(msg))) => {
+                    log::debug!("message {:?}", msg.data);
+                    return Some(crate::event::parse_message(&msg.data));
+                }
+                Some(Ok(Event::Open)) => {
+                    self.delay = self.options.initial_delay;
+                    if !std::mem::replace(&mut self.opened, true) {
+                        if self.report_open {
+                            return Some(vec![HueEvent::Reconnected]);
                         }
+                    } else if self.options.resync {
+                        log::info!("event stream reconnected, resyncing");
+                        return Some(vec![HueEvent::Reconnected, self.bridge.resync().await]);
+                    } else {
+                        log::info!("event stream reconnected");
+                        return Some(vec![HueEvent::Reconnected]);
                     }
-                    Ok(reqwest_eventsource::Event::Open) => None,
-                    Err(e) => Some(HueEvent::Error(format!("{:?}", e))),
                 }
-            }),
-        )
+                // retrying does not help when the application key is rejected
+                Some(Err(Error::InvalidStatusCode(status, _)))
+                    if status == reqwest::StatusCode::UNAUTHORIZED
+                        || status == reqwest::StatusCode::FORBIDDEN =>
+                {
+                    log::error!("event stream rejected with {status}, giving up");
+                    self.done = true;
+                    return Some(vec![HueEvent::Error(format!(
+                        "event stream rejected with {status}"
+                    ))]);
+                }
+                Some(Err(e)) => return Some(vec![HueEvent::Error(format!("{:?}", e))]),
+                None => {
+                    log::warn!("event stream closed, reconnecting in {:?}", self.delay);
+                    self.source = None;
+                }
+            }
+        }
+        None
     }
 }
 
-#[derive(Debug, Clone, serde::Deserialize)]
-struct EventEnvelope {
-    data: Vec<Event>,
+fn add_children(
+    mut current: Vec<ResourceIdentifier>,
+    added: &[ResourceIdentifier],
+) -> Vec<ResourceIdentifier> {
+    for child in added {
+        if !current.iter().any(|c| c.rid == child.rid) {
+            current.push(child.clone());
+        }
+    }
+    current
 }
 
-#[derive(Debug, Clone)]
-pub enum HueEvent {
-    Event { data: Vec<Event> },
-    Error(String),
+fn remove_children(
+    mut current: Vec<ResourceIdentifier>,
+    removed: &[ResourceIdentifier],
+) -> Vec<ResourceIdentifier> {
+    current.retain(|c| !removed.iter().any(|r| r.rid == c.rid));
+    current
 }
 
 #[derive(Debug, serde::Deserialize)]
@@ -797,3 +2918,34 @@ struct BridgeErrorInner {
 struct SuccessResponse<T> {
     success: T,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_gradient() {
+        let light: Light = serde_json::from_str(
+            r#"{"id":"l1","metadata":{"name":"Strip","archetype":"hue_lightstrip","function":"decorative"},
+            "service_id":0,"on":{"on":true},"gradient":{"points":[],"points_capable":3,
+            "mode":"interpolated_palette","mode_values":["interpolated_palette","random_pixelated"]}}"#,
+        )
+        .unwrap();
+        let gradient = |points: usize| {
+            (0..points).fold(CommandLightGradient::default(), |gradient, _| {
+                gradient.with_point(0.3, 0.3)
+            })
+        };
+        assert!(gradient(2).validate(&light).is_ok());
+        assert!(gradient(3)
+            .with_mode(GradientMode::RandomPixelated)
+            .validate(&light)
+            .is_ok());
+        assert!(gradient(1).validate(&light).is_err());
+        assert!(gradient(4).validate(&light).is_err());
+        assert!(gradient(2)
+            .with_mode(GradientMode::InterpolatedPaletteMirrored)
+            .validate(&light)
+            .is_err());
+    }
+}
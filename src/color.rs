@@ -0,0 +1,315 @@
+//! Conversions between the color models of applications and the CIE xy color space used by the
+//! bridge.
+//! ### Example
+//! ```
+//! let color = hueclient::color::hex_to_xy("#ff8000").unwrap();
+//! let command = hueclient::CommandLight::default()
+//!     .on()
+//!     .with_xy(color.xy.x, color.xy.y)
+//!     .with_brightness(color.brightness);
+//! ```
+use crate::{Gamut, HueError, XY};
+
+/// The white point of sRGB, which black and grey map to.
+const D65: (f64, f64) = (0.3127, 0.3290);
+
+/// A color as understood by the bridge.
+#[derive(Debug, Clone)]
+pub struct XyBrightness {
+    pub xy: XY,
+    /// Brightness in percent.
+    pub brightness: f32,
+}
+
+/// A color temperature in kelvin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Kelvin(pub u32);
+
+/// A color temperature in mirek, or micro reciprocal degrees, as used by the bridge. A higher
+/// mirek is a warmer color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Mirek(pub u16);
+
+impl Mirek {
+    /// The warmest and coolest color temperatures of the API. Each light supports a range within
+    /// these, given by its `MirekSchema`.
+    pub const MIN: Mirek = Mirek(153);
+    pub const MAX: Mirek = Mirek(500);
+}
+
+impl From<Kelvin> for Mirek {
+    fn from(kelvin: Kelvin) -> Self {
+        let kelvin = kelvin.0.max(1);
+        Mirek(((1_000_000 + kelvin / 2) / kelvin).min(u16::MAX as u32) as u16)
+    }
+}
+
+impl From<Mirek> for Kelvin {
+    fn from(mirek: Mirek) -> Self {
+        let mirek = mirek.0.max(1) as u32;
+        Kelvin((1_000_000 + mirek / 2) / mirek)
+    }
+}
+
+fn gamma_expand(c: f64) -> f64 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Converts a color with sRGB components between 0 and 1. The brightness is the largest of the
+/// components, so that saturated colors keep their intensity.
+pub fn srgb_to_xy(red: f64, green: f64, blue: f64) -> XyBrightness {
+    let [r, g, b] = [red, green, blue].map(|c| gamma_expand(c.clamp(0.0, 1.0)));
+    let x = 0.4124 * r + 0.3576 * g + 0.1805 * b;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = 0.0193 * r + 0.1192 * g + 0.9505 * b;
+    let sum = x + y + z;
+    let (x, y) = if sum > 0.0 { (x / sum, y / sum) } else { D65 };
+    XyBrightness {
+        xy: XY {
+            x: x as f32,
+            y: y as f32,
+        },
+        brightness: (red.max(green).max(blue).clamp(0.0, 1.0) * 100.0) as f32,
+    }
+}
+
+/// Converts an 8-bit sRGB color.
+pub fn rgb_to_xy(red: u8, green: u8, blue: u8) -> XyBrightness {
+    srgb_to_xy(
+        red as f64 / 255.0,
+        green as f64 / 255.0,
+        blue as f64 / 255.0,
+    )
+}
+
+/// Parses a color written as `RRGGBB` or `#RRGGBB`.
+pub fn parse_hex(hex: &str) -> crate::Result<(u8, u8, u8)> {
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    let component = |i: usize| {
+        digits
+            .get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    match (digits.len(), component(0), component(2), component(4)) {
+        (6, Some(red), Some(green), Some(blue)) => Ok((red, green, blue)),
+        _ => Err(HueError::invalid_command(format!(
+            "expected a color as RRGGBB, got {hex}"
+        ))),
+    }
+}
+
+/// Converts a color written as `RRGGBB` or `#RRGGBB`.
+pub fn hex_to_xy(hex: &str) -> crate::Result<XyBrightness> {
+    let (red, green, blue) = parse_hex(hex)?;
+    Ok(rgb_to_xy(red, green, blue))
+}
+
+/// Converts a color given by its hue in degrees, and its saturation and value between 0 and 1.
+pub fn hsv_to_xy(hue: f64, saturation: f64, value: f64) -> XyBrightness {
+    let (saturation, value) = (saturation.clamp(0.0, 1.0), value.clamp(0.0, 1.0));
+    let sector = hue.rem_euclid(360.0) / 60.0;
+    let chroma = value * saturation;
+    let second = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, second, 0.0),
+        1 => (second, chroma, 0.0),
+        2 => (0.0, chroma, second),
+        3 => (0.0, second, chroma),
+        4 => (second, 0.0, chroma),
+        _ => (chroma, 0.0, second),
+    };
+    let min = value - chroma;
+    srgb_to_xy(r + min, g + min, b + min)
+}
+
+fn gamma_compress(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_u8(c: f64) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Converts a color as understood by the bridge to 8-bit sRGB. Colors outside of sRGB are
+/// clipped, and the brightest component is scaled to `brightness`, mirroring `srgb_to_xy`.
+pub fn xy_to_rgb(xy: &XY, brightness: f32) -> (u8, u8, u8) {
+    let (x, y) = (xy.x as f64, xy.y as f64);
+    if y <= 0.0 {
+        return (0, 0, 0);
+    }
+    let (big_x, big_z) = (x / y, (1.0 - x - y) / y);
+    let linear = [
+        3.2406 * big_x - 1.5372 - 0.4986 * big_z,
+        -0.9689 * big_x + 1.8758 + 0.0415 * big_z,
+        0.0557 * big_x - 0.2040 + 1.0570 * big_z,
+    ]
+    .map(|c| c.max(0.0));
+    let max = linear.iter().cloned().fold(0.0, f64::max);
+    if max <= 0.0 {
+        return (0, 0, 0);
+    }
+    let scale = (brightness as f64 / 100.0).clamp(0.0, 1.0);
+    let [r, g, b] = linear.map(|c| to_u8(gamma_compress(c / max) * scale));
+    (r, g, b)
+}
+
+/// Approximates the color of a black body at this color temperature, in 8-bit sRGB, with the
+/// brightest component scaled to `brightness`.
+pub fn mirek_to_rgb(mirek: u16, brightness: f32) -> (u8, u8, u8) {
+    // Tanner Helland's fit of the black body curve, in hundreds of kelvin
+    let t = 10_000.0 / mirek.max(1) as f64;
+    let red = if t <= 66.0 {
+        255.0
+    } else {
+        329.698727446 * (t - 60.0).powf(-0.1332047592)
+    };
+    let green = if t <= 66.0 {
+        99.4708025861 * t.ln() - 161.1195681661
+    } else {
+        288.1221695283 * (t - 60.0).powf(-0.0755148492)
+    };
+    let blue = if t >= 66.0 {
+        255.0
+    } else if t <= 19.0 {
+        0.0
+    } else {
+        138.5177312231 * (t - 10.0).ln() - 305.0447927307
+    };
+    let scale = (brightness as f64 / 100.0).clamp(0.0, 1.0);
+    let [r, g, b] = [red, green, blue].map(|c| to_u8(c / 255.0 * scale));
+    (r, g, b)
+}
+
+/// Writes an 8-bit sRGB color as `#rrggbb`.
+pub fn rgb_to_hex(red: u8, green: u8, blue: u8) -> String {
+    format!("#{red:02x}{green:02x}{blue:02x}")
+}
+
+/// Returns whether a light with this gamut can show the color exactly.
+pub fn in_gamut(xy: &XY, gamut: &Gamut) -> bool {
+    let cross = |a: &XY, b: &XY| (b.x - a.x) * (xy.y - a.y) - (b.y - a.y) * (xy.x - a.x);
+    let sides = [
+        cross(&gamut.red, &gamut.green),
+        cross(&gamut.green, &gamut.blue),
+        cross(&gamut.blue, &gamut.red),
+    ];
+    sides.iter().all(|&s| s >= 0.0) || sides.iter().all(|&s| s <= 0.0)
+}
+
+/// Returns the color itself if it is in the gamut, or else the closest color a light with this
+/// gamut can show.
+pub fn clamp_to_gamut(xy: &XY, gamut: &Gamut) -> XY {
+    if in_gamut(xy, gamut) {
+        return xy.clone();
+    }
+    let closest_on_edge = |a: &XY, b: &XY| {
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let t = (((xy.x - a.x) * dx + (xy.y - a.y) * dy) / (dx * dx + dy * dy)).clamp(0.0, 1.0);
+        XY {
+            x: a.x + t * dx,
+            y: a.y + t * dy,
+        }
+    };
+    let distance = |p: &XY| (p.x - xy.x).powi(2) + (p.y - xy.y).powi(2);
+    [
+        closest_on_edge(&gamut.red, &gamut.green),
+        closest_on_edge(&gamut.green, &gamut.blue),
+        closest_on_edge(&gamut.blue, &gamut.red),
+    ]
+    .into_iter()
+    .min_by(|a, b| distance(a).total_cmp(&distance(b)))
+    .expect("a triangle has three edges")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(xy: &XY, x: f32, y: f32) {
+        assert!(
+            (xy.x - x).abs() < 0.001 && (xy.y - y).abs() < 0.001,
+            "expected ({x}, {y}), got {xy:?}"
+        );
+    }
+
+    #[test]
+    fn test_conversions() {
+        let red = rgb_to_xy(255, 0, 0);
+        assert_close(&red.xy, 0.64, 0.33);
+        assert_eq!(red.brightness, 100.0);
+        assert_close(&hex_to_xy("#FFFFFF").unwrap().xy, 0.3127, 0.329);
+        assert_close(&rgb_to_xy(0, 0, 0).xy, 0.3127, 0.329);
+        assert_close(&hsv_to_xy(240.0, 1.0, 0.5).xy, 0.15, 0.06);
+        assert_eq!(hsv_to_xy(240.0, 1.0, 0.5).brightness, 50.0);
+        assert!(hex_to_xy("12345").is_err());
+        assert!(hex_to_xy("12345g").is_err());
+    }
+
+    #[test]
+    fn test_color_temperature() {
+        assert_eq!(Mirek::from(Kelvin(2700)), Mirek(370));
+        assert_eq!(Mirek::from(Kelvin(6500)), Mirek(154));
+        assert_eq!(Kelvin::from(Mirek(370)), Kelvin(2703));
+        assert_eq!(Mirek::from(Kelvin(10)), Mirek(u16::MAX));
+        assert_eq!(Kelvin::from(Mirek(0)), Kelvin(1_000_000));
+    }
+
+    #[test]
+    fn test_reverse_conversions() {
+        for (red, green, blue) in [(255, 0, 0), (255, 128, 0), (20, 40, 255), (255, 255, 255)] {
+            let color = rgb_to_xy(red, green, blue);
+            let (r, g, b) = xy_to_rgb(&color.xy, color.brightness);
+            assert!(
+                r.abs_diff(red) <= 2 && g.abs_diff(green) <= 2 && b.abs_diff(blue) <= 2,
+                "expected {red} {green} {blue}, got {r} {g} {b}"
+            );
+        }
+        let (r, g, b) = xy_to_rgb(&XY { x: 0.64, y: 0.33 }, 50.0);
+        assert!(r.abs_diff(128) <= 1 && g == 0 && b == 0, "got {r} {g} {b}");
+        assert_eq!(rgb_to_hex(255, 128, 0), "#ff8000");
+
+        let (r, g, b) = mirek_to_rgb(153, 100.0);
+        assert!(r == 255 && g > 245 && b > 245, "got {r} {g} {b}");
+        let (r, g, b) = mirek_to_rgb(500, 100.0);
+        assert!(r == 255 && g < 150 && b < 20, "got {r} {g} {b}");
+    }
+
+    #[test]
+    fn test_clamp_to_gamut() {
+        // gamut C
+        let gamut = Gamut {
+            red: XY {
+                x: 0.6915,
+                y: 0.3083,
+            },
+            green: XY { x: 0.17, y: 0.7 },
+            blue: XY {
+                x: 0.1532,
+                y: 0.0475,
+            },
+        };
+        let inside = XY { x: 0.4, y: 0.4 };
+        assert!(in_gamut(&inside, &gamut));
+        assert_close(&clamp_to_gamut(&inside, &gamut), 0.4, 0.4);
+        // beyond the blue corner
+        assert_close(
+            &clamp_to_gamut(&XY { x: 0.1, y: 0.0 }, &gamut),
+            0.1532,
+            0.0475,
+        );
+        // beyond the red-green edge, projected onto it
+        assert_close(
+            &clamp_to_gamut(&XY { x: 0.5, y: 0.6 }, &gamut),
+            0.4289,
+            0.5055,
+        );
+    }
+}
@@ -1,112 +1,415 @@
-use crate::{HueError, HueError::DiscoveryError};
-use futures::executor::block_on;
-use futures_util::{pin_mut, stream::StreamExt};
-use mdns::{Record, RecordKind};
+use crate::{Bridge, CertificatePin, CertificatePolicy, HueError, HueError::DiscoveryError};
+use futures_util::{pin_mut, stream::Stream, stream::StreamExt};
 use serde_json::{Map, Value};
-use std::{net::IpAddr, time::Duration};
+use std::{
+    net::{IpAddr, Ipv4Addr},
+    time::Duration,
+};
+
+/// Configures how `Bridge::discover` and `Bridge::discover_all` look for bridges.
+/// ### Example
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// let options = hueclient::DiscoveryOptions::default()
+///     .with_timeout(std::time::Duration::from_secs(2))
+///     .without_n_upnp();
+/// let maybe_bridge = hueclient::Bridge::discover(options).await;
+/// # })
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiscoveryOptions {
+    /// How long to wait for mDNS answers, and for the discovery endpoint to respond.
+    pub timeout: Duration,
+    /// Interval between two mDNS queries.
+    pub query_interval: Duration,
+    /// Whether to also ask the Philips discovery endpoint (discovery.meethue.com).
+    pub n_upnp: bool,
+    /// A network, as an address and a prefix length, whose hosts are probed one by one when
+    /// neither mDNS nor the discovery endpoint found a bridge.
+    pub subnet: Option<(Ipv4Addr, u8)>,
+    /// How long to wait for a single host of `subnet` to answer.
+    pub probe_timeout: Duration,
+}
+
+impl Default for DiscoveryOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            query_interval: Duration::from_secs(1),
+            n_upnp: true,
+            subnet: None,
+            probe_timeout: Duration::from_secs(1),
+        }
+    }
+}
+
+impl DiscoveryOptions {
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn with_query_interval(self, query_interval: Duration) -> Self {
+        Self {
+            query_interval,
+            ..self
+        }
+    }
+
+    /// Only use mDNS, for networks where the bridge should not be looked up over the internet.
+    pub fn without_n_upnp(self) -> Self {
+        Self {
+            n_upnp: false,
+            ..self
+        }
+    }
+
+    /// Falls back to probing every host of a network, for networks where both mDNS and the
+    /// discovery endpoint are blocked. `192.168.1.0/24` is
+    /// `with_subnet_scan([192, 168, 1, 0].into(), 24)`. The scan ends when `timeout` expires, so
+    /// larger networks need a larger timeout.
+    pub fn with_subnet_scan(self, network: Ipv4Addr, prefix_len: u8) -> Self {
+        Self {
+            subnet: Some((network, prefix_len)),
+            ..self
+        }
+    }
+
+    pub fn with_probe_timeout(self, probe_timeout: Duration) -> Self {
+        Self {
+            probe_timeout,
+            ..self
+        }
+    }
+}
 
 // As Per instrucitons at
 // https://developers.meethue.com/develop/application-design-guidance/hue-bridge-discovery/
-pub async fn discover_hue_bridge() -> Result<IpAddr, HueError> {
-    let bridge_ftr = discover_hue_bridge_m_dns();
-    let bridge = block_on(bridge_ftr);
-    match bridge {
-        Ok(bridge_ip) => {
-            log::info!("discovered bridge at {bridge_ip} using mDNS");
-            Ok(bridge_ip)
-        }
-        Err(mdns_error) => {
-            log::debug!(
-                "Error in mDNS discovery: {}, falling back to n-upnp",
-                mdns_error
-            );
-            let n_upnp_result = discover_hue_bridge_n_upnp().await;
-            match n_upnp_result {
-                Ok(bridge_ip) => {
-                    log::info!("discovered bridge at {bridge_ip} using n-upnp");
-                    Ok(bridge_ip)
-                }
-                Err(nupnp_error) => {
-                    log::debug!("Failed to discover bridge using or n-upnp: {nupnp_error}");
-                    Err(DiscoveryError {
-                        msg: "Could not discover bridge".into(),
-                    })?
-                }
+pub async fn discover_hue_bridge(options: &DiscoveryOptions) -> Result<DiscoveredBridge, HueError> {
+    match discover_hue_bridge_m_dns(options).await {
+        Ok(bridge) => {
+            log::info!("discovered bridge at {} using mDNS", bridge.ip);
+            return Ok(bridge);
+        }
+        Err(mdns_error) => log::debug!("Error in mDNS discovery: {}", mdns_error),
+    }
+    if options.n_upnp {
+        match with_timeout(options.timeout, discover_hue_bridge_n_upnp()).await {
+            Ok(bridge) => {
+                log::info!("discovered bridge at {} using n-upnp", bridge.ip);
+                return Ok(bridge);
             }
+            Err(nupnp_error) => log::debug!("Error in n-upnp discovery: {nupnp_error}"),
         }
     }
+    if let Some((network, prefix_len)) = options.subnet {
+        let bridges = scan_subnet(network, prefix_len, options);
+        pin_mut!(bridges);
+        if let Ok(Some(bridge)) = tokio::time::timeout(options.timeout, bridges.next()).await {
+            log::info!("discovered bridge at {} using a subnet scan", bridge.ip);
+            return Ok(bridge);
+        }
+        log::debug!("No bridge found in {network}/{prefix_len}");
+    }
+    Err(DiscoveryError {
+        msg: "Could not discover bridge".into(),
+    })
 }
 
-pub async fn discover_hue_bridge_n_upnp() -> Result<IpAddr, HueError> {
-    let objects: Vec<Map<String, Value>> = reqwest::get("https://discovery.meethue.com/")
-        .await?
-        .json()
-        .await?;
-
-    if objects.is_empty() {
-        Err(DiscoveryError {
-            msg: "expected non-empty array".into(),
-        })?
-    }
-    let object = &objects[0];
+// Runs a discovery future on the caller's runtime, failing once the timeout expires
+async fn with_timeout<T>(
+    timeout: Duration,
+    future: impl std::future::Future<Output = Result<T, HueError>>,
+) -> Result<T, HueError> {
+    tokio::time::timeout(timeout, future)
+        .await
+        .unwrap_or_else(|_| {
+            Err(DiscoveryError {
+                msg: "Timed out".into(),
+            })
+        })
+}
 
-    let ip = object.get("internalipaddress").ok_or(DiscoveryError {
-        msg: "Expected internalipaddress".into(),
-    })?;
-    Ok(ip
-        .as_str()
+pub async fn discover_hue_bridge_n_upnp() -> Result<DiscoveredBridge, HueError> {
+    discover_all_hue_bridges_n_upnp()
+        .await?
+        .into_iter()
+        .next()
         .ok_or(DiscoveryError {
-            msg: "expect a string in internalipaddress".into(),
-        })?
-        .parse()?)
+            msg: "expected non-empty array".into(),
+        })
 }
 
 // Define the service name for hue bridge
 const SERVICE_NAME: &str = "_hue._tcp.local";
 
 // Define a function that discovers a hue bridge using mDNS
-pub async fn discover_hue_bridge_m_dns() -> Result<IpAddr, HueError> {
-    // Iterate through responses from each hue bridge device, asking for new devices every 15s
-    let stream_disc = mdns::discover::all(SERVICE_NAME, Duration::from_secs(1));
-    let stream = match stream_disc {
-        Ok(s) => s.listen(),
-        Err(_e) => {
-            return Err(DiscoveryError {
-                msg: _e.to_string(),
+pub async fn discover_hue_bridge_m_dns(
+    options: &DiscoveryOptions,
+) -> Result<DiscoveredBridge, HueError> {
+    let mut found = None;
+    query_m_dns(options, |bridge| {
+        found = Some(bridge);
+        false
+    })
+    .await?;
+    found.ok_or(DiscoveryError {
+        msg: "No response from bridge".into(),
+    })
+}
+
+// Asks for bridges over mDNS every query interval, passing each answer to `on_bridge` until it
+// returns false or the timeout expires. Queries are sent from an ephemeral port, so bridges answer
+// directly to it (a "legacy unicast" query) and the socket is driven by the caller's runtime.
+async fn query_m_dns(
+    options: &DiscoveryOptions,
+    mut on_bridge: impl FnMut(DiscoveredBridge) -> bool,
+) -> Result<(), HueError> {
+    const MDNS_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::new(224, 0, 0, 251), 5353);
+    let io_err = |e: std::io::Error| DiscoveryError { msg: e.to_string() };
+    let mut query = dns_parser::Builder::new_query(0, false);
+    query.add_question(
+        SERVICE_NAME,
+        false,
+        dns_parser::QueryType::PTR,
+        dns_parser::QueryClass::IN,
+    );
+    let query = query.build().map_err(|_| DiscoveryError {
+        msg: "mDNS query does not fit in a packet".into(),
+    })?;
+    let socket = tokio::net::UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .map_err(io_err)?;
+    let deadline = tokio::time::sleep(options.timeout);
+    pin_mut!(deadline);
+    let mut interval = tokio::time::interval(options.query_interval);
+    let mut buf = [0u8; 4096];
+    loop {
+        tokio::select! {
+            _ = &mut deadline => return Ok(()),
+            _ = interval.tick() => {
+                socket.send_to(&query, MDNS_ADDR).await.map_err(io_err)?;
+            }
+            received = socket.recv_from(&mut buf) => {
+                let (len, from) = received.map_err(io_err)?;
+                if let Some(bridge) = to_discovered_bridge(&buf[..len], from.ip()) {
+                    if !on_bridge(bridge) {
+                        return Ok(());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A bridge found on the local network by `Bridge::discover_all`. Converting it into an
+/// `UnauthBridge` only accepts the certificate of this bridge, when its id is known.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredBridge {
+    /// The IP-address of the bridge.
+    pub ip: IpAddr,
+    /// The HTTPS port of the bridge.
+    pub port: u16,
+    /// The unique id of the bridge, such as `001788fffe6a5b4c`, when the discovery method
+    /// reported it.
+    pub id: Option<String>,
+}
+
+// Discovers every bridge using both mDNS and n-upnp, and merges the results
+pub async fn discover_all_hue_bridges(
+    options: &DiscoveryOptions,
+) -> Result<Vec<DiscoveredBridge>, HueError> {
+    let n_upnp = async {
+        if options.n_upnp {
+            with_timeout(options.timeout, discover_all_hue_bridges_n_upnp()).await
+        } else {
+            Ok(vec![])
+        }
+    };
+    let (m_dns, n_upnp) = futures::join!(discover_all_hue_bridges_m_dns(options), n_upnp);
+    let bridges = match (m_dns, n_upnp) {
+        (Err(mdns_error), Err(nupnp_error)) => {
+            log::debug!(
+                "Failed to discover bridges using mDNS: {mdns_error} or n-upnp: {nupnp_error}"
+            );
+            Err(DiscoveryError {
+                msg: "Could not discover bridges".into(),
             })
         }
+        (m_dns, n_upnp) => {
+            let m_dns = m_dns.unwrap_or_else(|e| {
+                log::debug!("Error in mDNS discovery: {e}");
+                vec![]
+            });
+            let n_upnp = n_upnp.unwrap_or_else(|e| {
+                log::debug!("Error in n-upnp discovery: {e}");
+                vec![]
+            });
+            Ok(merge_bridges(m_dns.into_iter().chain(n_upnp)))
+        }
     };
-    pin_mut!(stream);
-    let response = async_std::future::timeout(Duration::from_secs(5), stream.next()).await;
-    match response {
-        Ok(Some(Ok(response))) => {
-            // Get the first IP address from the response
-            let ip = response
-                .records()
-                .filter_map(to_ip_addr)
-                .next()
-                .ok_or(DiscoveryError {
-                    msg: "No IP address found in response".into(),
-                })?;
-            Ok(ip)
+    match (bridges, options.subnet) {
+        (Ok(bridges), _) if !bridges.is_empty() => Ok(bridges),
+        (bridges, Some((network, prefix_len))) => {
+            let deadline = tokio::time::Instant::now() + options.timeout;
+            let scan = scan_subnet(network, prefix_len, options);
+            pin_mut!(scan);
+            let mut found = vec![];
+            while let Ok(Some(bridge)) = tokio::time::timeout_at(deadline, scan.next()).await {
+                found.push(bridge);
+            }
+            match bridges {
+                Err(e) if found.is_empty() => Err(e),
+                _ => Ok(merge_bridges(found)),
+            }
         }
-        Ok(Some(Err(e))) => Err(DiscoveryError { msg: e.to_string() }),
-        Ok(None) => Err(DiscoveryError {
-            msg: "No response from bridge".into(),
-        }),
-        Err(_e) => Err(DiscoveryError {
-            msg: "No response from bridge".into(),
-        }),
+        (bridges, None) => bridges,
     }
 }
 
-// Define a helper function that converts a record to an IP address
-fn to_ip_addr(record: &Record) -> Option<IpAddr> {
-    match record.kind {
-        RecordKind::A(addr) => Some(addr.into()),
-        RecordKind::AAAA(addr) => Some(addr.into()),
-        _ => None,
+// Probes every host of the network concurrently, yielding the bridges as they answer
+fn scan_subnet(
+    network: Ipv4Addr,
+    prefix_len: u8,
+    options: &DiscoveryOptions,
+) -> impl Stream<Item = DiscoveredBridge> {
+    const CONCURRENT_PROBES: usize = 64;
+    let probe_timeout = options.probe_timeout;
+    futures::stream::iter(subnet_hosts(network, prefix_len))
+        .map(move |ip| async move {
+            // old bridges use a self-signed certificate, which is checked once connecting to them
+            let bridge = Bridge::for_ip(ip)
+                .with_certificate_policy(CertificatePolicy::TrustOnFirstUse(CertificatePin::new()));
+            match tokio::time::timeout(probe_timeout, bridge.probe()).await {
+                Ok(Ok(info)) => Some(DiscoveredBridge {
+                    ip: ip.into(),
+                    port: 443,
+                    id: Some(info.bridgeid.to_lowercase()),
+                }),
+                _ => None,
+            }
+        })
+        .buffer_unordered(CONCURRENT_PROBES)
+        .filter_map(futures::future::ready)
+}
+
+// Lists the host addresses of a network, leaving out its network and broadcast addresses
+fn subnet_hosts(network: Ipv4Addr, prefix_len: u8) -> impl Iterator<Item = Ipv4Addr> {
+    let prefix_len = prefix_len.min(32) as u32;
+    let mask = u32::MAX.checked_shl(32 - prefix_len).unwrap_or(0);
+    let first = u32::from(network) & mask;
+    let last = first | !mask;
+    let hosts = if prefix_len >= 31 {
+        first..=last
+    } else {
+        first + 1..=last - 1
+    };
+    hosts.map(Ipv4Addr::from)
+}
+
+pub async fn discover_all_hue_bridges_n_upnp() -> Result<Vec<DiscoveredBridge>, HueError> {
+    let objects: Vec<Map<String, Value>> = reqwest::get("https://discovery.meethue.com/")
+        .await?
+        .json()
+        .await?;
+    objects
+        .iter()
+        .map(|object| {
+            let ip = object
+                .get("internalipaddress")
+                .and_then(Value::as_str)
+                .ok_or(DiscoveryError {
+                    msg: "expect a string in internalipaddress".into(),
+                })?
+                .parse()?;
+            Ok(DiscoveredBridge {
+                ip,
+                port: object
+                    .get("port")
+                    .and_then(Value::as_u64)
+                    .and_then(|port| u16::try_from(port).ok())
+                    .unwrap_or(443),
+                id: object
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .map(|id| id.to_lowercase()),
+            })
+        })
+        .collect()
+}
+
+// Collects the responses of every bridge answering over mDNS until the timeout expires
+pub async fn discover_all_hue_bridges_m_dns(
+    options: &DiscoveryOptions,
+) -> Result<Vec<DiscoveredBridge>, HueError> {
+    let mut bridges = vec![];
+    query_m_dns(options, |bridge| {
+        bridges.push(bridge);
+        true
+    })
+    .await?;
+    Ok(merge_bridges(bridges))
+}
+
+// Reads the bridge described by an mDNS response, falling back to the address it was sent from
+fn to_discovered_bridge(response: &[u8], from: IpAddr) -> Option<DiscoveredBridge> {
+    use dns_parser::RData;
+    let packet = dns_parser::Packet::parse(response).ok()?;
+    let records = || packet.answers.iter().chain(&packet.additional);
+    let is_hue = records().any(|record| match &record.data {
+        RData::PTR(_) => record.name.to_string() == SERVICE_NAME,
+        _ => false,
+    });
+    if !is_hue {
+        return None;
     }
+    // prefer IPv4 addresses, the bridge API is rarely reachable over IPv6
+    let ip = records()
+        .filter_map(|record| match record.data {
+            RData::A(a) => Some(IpAddr::from(a.0)),
+            RData::AAAA(aaaa) => Some(IpAddr::from(aaaa.0)),
+            _ => None,
+        })
+        .min_by_key(|ip| ip.is_ipv6())
+        .unwrap_or(from);
+    Some(DiscoveredBridge {
+        ip,
+        port: records()
+            .find_map(|record| match &record.data {
+                RData::SRV(srv) => Some(srv.port),
+                _ => None,
+            })
+            .unwrap_or(443),
+        id: records()
+            .filter_map(|record| match &record.data {
+                RData::TXT(txt) => Some(txt.iter()),
+                _ => None,
+            })
+            .flatten()
+            .find_map(|txt| txt.strip_prefix(b"bridgeid="))
+            .map(|id| String::from_utf8_lossy(id).to_lowercase()),
+    })
+}
+
+// Deduplicates bridges by id, or by IP-address when the id is unknown
+fn merge_bridges(bridges: impl IntoIterator<Item = DiscoveredBridge>) -> Vec<DiscoveredBridge> {
+    let mut merged: Vec<DiscoveredBridge> = vec![];
+    for bridge in bridges {
+        let existing = merged
+            .iter_mut()
+            .find(|known| match (&known.id, &bridge.id) {
+                (Some(a), Some(b)) => a == b,
+                _ => known.ip == bridge.ip,
+            });
+        match existing {
+            Some(known) => {
+                if known.id.is_none() {
+                    known.id = bridge.id;
+                }
+            }
+            None => merged.push(bridge),
+        }
+    }
+    merged
 }
 
 #[cfg(test)]
@@ -116,9 +419,84 @@ mod tests {
     #[tokio::test]
     #[ignore]
     async fn test_discover_hue_bridge() {
-        let ip = discover_hue_bridge().await;
+        let ip = discover_hue_bridge(&DiscoveryOptions::default()).await;
         assert!(ip.is_ok());
         let ip = ip.unwrap();
-        assert_eq!(ip.to_string(), "192.168.1.149");
+        assert_eq!(ip.ip.to_string(), "192.168.1.149");
+    }
+
+    #[test]
+    fn test_merge_bridges() {
+        let bridge = |ip: [u8; 4], id: Option<&str>| DiscoveredBridge {
+            ip: ip.into(),
+            port: 443,
+            id: id.map(String::from),
+        };
+        let merged = merge_bridges(vec![
+            bridge([192, 168, 1, 2], None),
+            bridge([192, 168, 1, 3], Some("001788fffe000003")),
+            bridge([192, 168, 1, 2], Some("001788fffe000002")),
+            bridge([10, 0, 0, 3], Some("001788fffe000003")),
+        ]);
+        assert_eq!(
+            merged,
+            vec![
+                bridge([192, 168, 1, 2], Some("001788fffe000002")),
+                bridge([192, 168, 1, 3], Some("001788fffe000003")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_subnet_hosts() {
+        let hosts: Vec<_> = subnet_hosts([192, 168, 1, 77].into(), 24).collect();
+        assert_eq!(hosts.len(), 254);
+        assert_eq!(hosts[0], Ipv4Addr::new(192, 168, 1, 1));
+        assert_eq!(hosts[253], Ipv4Addr::new(192, 168, 1, 254));
+        let hosts: Vec<_> = subnet_hosts([10, 0, 0, 5].into(), 32).collect();
+        assert_eq!(hosts, vec![Ipv4Addr::new(10, 0, 0, 5)]);
+    }
+
+    #[test]
+    fn test_to_discovered_bridge() {
+        // a response to the PTR query, with the TXT and A records of the bridge
+        fn name(name: &str) -> Vec<u8> {
+            let mut bytes: Vec<u8> = name
+                .split('.')
+                .flat_map(|label| std::iter::once(label.len() as u8).chain(label.bytes()))
+                .collect();
+            bytes.push(0);
+            bytes
+        }
+        fn record(name: Vec<u8>, rtype: u16, data: Vec<u8>) -> Vec<u8> {
+            let mut bytes = name;
+            bytes.extend(rtype.to_be_bytes());
+            bytes.extend([0, 1, 0, 0, 0x11, 0x94]);
+            bytes.extend((data.len() as u16).to_be_bytes());
+            bytes.extend(data);
+            bytes
+        }
+        let instance = "Hue Bridge - 6A5B4C._hue._tcp.local";
+        let mut txt = b"\x19bridgeid=001788FFFE6A5B4C".to_vec();
+        txt.extend(b"\x0emodelid=BSB002");
+        let mut response = vec![0, 0, 0x84, 0, 0, 0, 0, 1, 0, 0, 0, 2];
+        response.extend(record(name(SERVICE_NAME), 12, name(instance)));
+        response.extend(record(name(instance), 16, txt));
+        response.extend(record(
+            name("ecb5fa6a5b4c.local"),
+            1,
+            vec![192, 168, 1, 149],
+        ));
+
+        let from = IpAddr::from([192, 168, 1, 1]);
+        assert_eq!(
+            to_discovered_bridge(&response, from),
+            Some(DiscoveredBridge {
+                ip: [192, 168, 1, 149].into(),
+                port: 443,
+                id: Some("001788fffe6a5b4c".into()),
+            })
+        );
+        assert_eq!(to_discovered_bridge(&response[..20], from), None);
     }
 }
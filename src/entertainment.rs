@@ -0,0 +1,652 @@
+use crate::{ResourceIdentifier, XY};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntertainmentConfigurationType {
+    Screen,
+    Monitor,
+    Music,
+    #[serde(rename = "3dspace")]
+    Space3d,
+    Other,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntertainmentStatus {
+    /// An application is streaming to this configuration.
+    Active,
+    Inactive,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A point in the entertainment area, each coordinate between -1 and 1.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// A segment of an entertainment service, such as one pixel of a gradient light strip.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentReference {
+    pub service: ResourceIdentifier,
+    pub index: u32,
+}
+
+/// A channel of an entertainment configuration. Frames address lights by channel id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntertainmentChannel {
+    pub channel_id: u8,
+    pub position: Position,
+    pub members: Vec<SegmentReference>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntertainmentConfigurationMetadata {
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamProxyMode {
+    /// The bridge picks the light forwarding the stream to the others.
+    Auto,
+    Manual,
+    #[serde(other)]
+    Unknown,
+}
+
+/// The light forwarding the stream to the other lights of the configuration over Zigbee.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamProxy {
+    pub mode: StreamProxyMode,
+    /// The entertainment service acting as proxy. Only needed when creating a configuration
+    /// with the manual mode.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<ResourceIdentifier>,
+}
+
+/// Where the lights of an entertainment service are placed in the entertainment area.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceLocation {
+    /// The entertainment service of the light, not the light itself.
+    pub service: ResourceIdentifier,
+    /// One position, or the positions of both ends of a gradient light.
+    pub positions: Vec<Position>,
+    /// Relative brightness of the light, between 0 and 1, to even out lights of different
+    /// strengths.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equalization_factor: Option<f64>,
+}
+
+impl ServiceLocation {
+    pub fn new(service: &str, positions: Vec<Position>) -> Self {
+        Self {
+            service: ResourceIdentifier {
+                rid: service.to_string(),
+                rtype: "entertainment".to_string(),
+            },
+            positions,
+            equalization_factor: None,
+        }
+    }
+
+    pub fn with_equalization_factor(self, equalization_factor: f64) -> Self {
+        Self {
+            equalization_factor: Some(equalization_factor),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntertainmentLocations {
+    pub service_locations: Vec<ServiceLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntertainmentConfiguration {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub metadata: EntertainmentConfigurationMetadata,
+    pub configuration_type: EntertainmentConfigurationType,
+    pub status: EntertainmentStatus,
+    /// The application currently streaming, when `status` is active.
+    pub active_streamer: Option<ResourceIdentifier>,
+    pub stream_proxy: Option<StreamProxy>,
+    /// The channels computed by the bridge from the locations, with their positions.
+    pub channels: Vec<EntertainmentChannel>,
+    #[serde(default)]
+    pub locations: EntertainmentLocations,
+    /// The lights of the configuration.
+    #[serde(default)]
+    pub light_services: Vec<ResourceIdentifier>,
+}
+
+impl EntertainmentConfiguration {
+    pub fn channel(&self, channel_id: u8) -> Option<&EntertainmentChannel> {
+        self.channels.iter().find(|c| c.channel_id == channel_id)
+    }
+
+    /// Returns the channels driving segments of this entertainment service.
+    pub fn channels_of(&self, service: &str) -> Vec<&EntertainmentChannel> {
+        self.channels
+            .iter()
+            .filter(|c| c.members.iter().any(|m| m.service.rid == service))
+            .collect()
+    }
+
+    /// Returns the location of this entertainment service.
+    pub fn location(&self, service: &str) -> Option<&ServiceLocation> {
+        self.locations
+            .service_locations
+            .iter()
+            .find(|l| l.service.rid == service)
+    }
+}
+
+/// Describes an entertainment configuration to be created with
+/// `Bridge::create_entertainment_configuration`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandCreateEntertainmentConfiguration {
+    pub metadata: EntertainmentConfigurationMetadata,
+    pub configuration_type: EntertainmentConfigurationType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_proxy: Option<StreamProxy>,
+    pub locations: EntertainmentLocations,
+}
+
+impl CommandCreateEntertainmentConfiguration {
+    pub fn new(
+        name: impl Into<String>,
+        configuration_type: EntertainmentConfigurationType,
+    ) -> Self {
+        Self {
+            metadata: EntertainmentConfigurationMetadata { name: name.into() },
+            configuration_type,
+            stream_proxy: None,
+            locations: EntertainmentLocations::default(),
+        }
+    }
+
+    /// Adds a light, given by its entertainment service, at these positions.
+    pub fn with_location(mut self, location: ServiceLocation) -> Self {
+        self.locations.service_locations.push(location);
+        self
+    }
+
+    pub fn with_stream_proxy(self, stream_proxy: StreamProxy) -> Self {
+        Self {
+            stream_proxy: Some(stream_proxy),
+            ..self
+        }
+    }
+}
+
+/// Describes the changes to apply to an existing entertainment configuration with
+/// `Bridge::update_entertainment_configuration`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CommandUpdateEntertainmentConfiguration {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<EntertainmentConfigurationMetadata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub configuration_type: Option<EntertainmentConfigurationType>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_proxy: Option<StreamProxy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub locations: Option<EntertainmentLocations>,
+}
+
+impl CommandUpdateEntertainmentConfiguration {
+    pub fn with_name(self, name: impl Into<String>) -> Self {
+        Self {
+            metadata: Some(EntertainmentConfigurationMetadata { name: name.into() }),
+            ..self
+        }
+    }
+
+    pub fn with_configuration_type(
+        self,
+        configuration_type: EntertainmentConfigurationType,
+    ) -> Self {
+        Self {
+            configuration_type: Some(configuration_type),
+            ..self
+        }
+    }
+
+    pub fn with_stream_proxy(self, stream_proxy: StreamProxy) -> Self {
+        Self {
+            stream_proxy: Some(stream_proxy),
+            ..self
+        }
+    }
+
+    /// Replaces the lights of the configuration and their positions. The bridge computes the
+    /// channels again.
+    pub fn with_locations(self, service_locations: Vec<ServiceLocation>) -> Self {
+        Self {
+            locations: Some(EntertainmentLocations { service_locations }),
+            ..self
+        }
+    }
+}
+
+/// A range of pixels of a gradient light, driven as one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntertainmentSegment {
+    pub start: u32,
+    pub length: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntertainmentSegments {
+    /// Whether the segments can be changed with `Bridge::set_entertainment_segments`.
+    pub configurable: bool,
+    pub max_segments: u32,
+    pub segments: Vec<EntertainmentSegment>,
+}
+
+/// The entertainment capabilities of a device, which is what entertainment configurations
+/// reference instead of lights.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntertainmentService {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: ResourceIdentifier,
+    /// Whether the device renders the stream, so can be added to an entertainment configuration.
+    pub renderer: bool,
+    /// Whether the device can forward the stream to other devices.
+    pub proxy: bool,
+    pub renderer_reference: Option<ResourceIdentifier>,
+    pub segments: Option<EntertainmentSegments>,
+}
+
+/// The colors of one HueStream frame. Channels left out of a frame keep their previous color.
+#[derive(Debug, Clone)]
+pub enum EntertainmentFrame {
+    /// The red, green and blue components of each channel, between 0 and 1.
+    Rgb(Vec<(u8, [f64; 3])>),
+    /// The CIE xy coordinates and the brightness of each channel, between 0 and 1.
+    Xy(Vec<(u8, XY, f64)>),
+}
+
+impl EntertainmentFrame {
+    /// The most channels the bridge accepts in a single frame.
+    pub const MAX_CHANNELS: usize = 20;
+
+    /// Encodes the frame as a HueStream version 2 message for the entertainment configuration
+    /// with this id. `EntertainmentStream::send` takes care of this, but it is handy when
+    /// streaming over another DTLS implementation.
+    pub fn encode(&self, configuration: &str, sequence: u8) -> crate::Result<Vec<u8>> {
+        fn scale(value: f64) -> [u8; 2] {
+            ((value.clamp(0.0, 1.0) * u16::MAX as f64).round() as u16).to_be_bytes()
+        }
+        if configuration.len() != 36 {
+            Err(crate::HueError::invalid_command(format!(
+                "expected an entertainment configuration id of 36 characters, got {configuration}"
+            )))?
+        }
+        let (color_space, channels): (u8, Vec<(u8, [f64; 3])>) = match self {
+            EntertainmentFrame::Rgb(channels) => (0x00, channels.clone()),
+            EntertainmentFrame::Xy(channels) => (
+                0x01,
+                channels
+                    .iter()
+                    .map(|(id, xy, brightness)| (*id, [xy.x as f64, xy.y as f64, *brightness]))
+                    .collect(),
+            ),
+        };
+        if channels.len() > Self::MAX_CHANNELS {
+            Err(crate::HueError::invalid_command(format!(
+                "a frame holds at most {} channels, got {}",
+                Self::MAX_CHANNELS,
+                channels.len()
+            )))?
+        }
+        let mut message = Vec::with_capacity(52 + 7 * channels.len());
+        message.extend_from_slice(b"HueStream");
+        // version 2.0, sequence id, two reserved bytes, color space and one more reserved byte
+        message.extend_from_slice(&[0x02, 0x00, sequence, 0x00, 0x00, color_space, 0x00]);
+        message.extend_from_slice(configuration.as_bytes());
+        for (id, values) in channels {
+            message.push(id);
+            for value in values {
+                message.extend_from_slice(&scale(value));
+            }
+        }
+        Ok(message)
+    }
+}
+
+#[cfg(feature = "entertainment")]
+pub use stream::EntertainmentStream;
+
+#[cfg(feature = "entertainment")]
+mod stream {
+    use super::EntertainmentFrame;
+    use crate::{Bridge, HueError};
+    use openssl::ssl::{Ssl, SslContext, SslMethod, SslStream, SslVersion};
+    use std::io::{Read, Write};
+    use std::net::{SocketAddr, UdpSocket};
+    use std::time::Duration;
+
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+    const MTU: u32 = 1400;
+
+    pub(crate) fn stream_err(err: impl std::fmt::Display) -> HueError {
+        HueError::EntertainmentError {
+            msg: err.to_string(),
+        }
+    }
+
+    /// A connected UDP socket, read and written one datagram at a time as DTLS expects.
+    #[derive(Debug)]
+    pub(crate) struct UdpChannel(pub UdpSocket);
+
+    impl Read for UdpChannel {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.0.recv(buf)
+        }
+    }
+
+    impl Write for UdpChannel {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.send(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+        if !hex.len().is_multiple_of(2) {
+            return None;
+        }
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+            .collect()
+    }
+
+    /// A DTLS session streaming colors to an entertainment configuration, as returned by
+    /// `Bridge::open_entertainment_stream`. The bridge ends the session after 10 seconds without
+    /// frames, so keep sending even when nothing changes.
+    ///
+    /// Call `close` when done. Dropping the stream instead ends the DTLS session and stops the
+    /// entertainment configuration in a task spawned on the current tokio runtime, without
+    /// reporting failures. Outside of a runtime the configuration stays active until the bridge
+    /// times it out.
+    #[derive(Debug)]
+    pub struct EntertainmentStream {
+        stream: SslStream<UdpChannel>,
+        bridge: Bridge,
+        configuration: String,
+        sequence: u8,
+        ticker: tokio::time::Interval,
+        closed: bool,
+    }
+
+    impl EntertainmentStream {
+        pub(crate) async fn connect(
+            bridge: Bridge,
+            addr: SocketAddr,
+            configuration: &str,
+            frames_per_second: u32,
+        ) -> crate::Result<Self> {
+            let identity = bridge.application_key.clone();
+            let psk = bridge
+                .client_key
+                .as_deref()
+                .and_then(decode_hex)
+                .ok_or_else(|| {
+                    HueError::invalid_command(
+                        "streaming needs the client key generated during registration",
+                    )
+                })?;
+            let stream = tokio::task::spawn_blocking(move || handshake(addr, identity, psk))
+                .await
+                .map_err(stream_err)??;
+            let mut ticker =
+                tokio::time::interval(Duration::from_secs(1) / frames_per_second.max(1));
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            Ok(Self {
+                stream,
+                bridge,
+                configuration: configuration.to_string(),
+                sequence: 0,
+                ticker,
+                closed: false,
+            })
+        }
+
+        /// Sends a frame, waiting first until the previous one is one period old.
+        pub async fn send(&mut self, frame: &EntertainmentFrame) -> crate::Result<()> {
+            let message = frame.encode(&self.configuration, self.sequence)?;
+            self.ticker.tick().await;
+            // a single datagram, written without waiting for the bridge to answer
+            self.stream.ssl_write(&message).map_err(stream_err)?;
+            self.sequence = self.sequence.wrapping_add(1);
+            Ok(())
+        }
+
+        /// Ends the DTLS session and stops streaming to the entertainment configuration, giving
+        /// control of its lights back to the bridge.
+        pub async fn close(mut self) -> crate::Result<()> {
+            self.closed = true;
+            if let Err(e) = self.stream.shutdown() {
+                log::debug!("failed to end the DTLS session: {e}");
+            }
+            self.bridge.stop_entertainment(&self.configuration).await
+        }
+    }
+
+    impl Drop for EntertainmentStream {
+        fn drop(&mut self) {
+            if self.closed {
+                return;
+            }
+            if let Err(e) = self.stream.shutdown() {
+                log::debug!("failed to end the DTLS session: {e}");
+            }
+            let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+                log::warn!(
+                    "entertainment stream dropped outside of a runtime, configuration {} stays \
+                     active until the bridge times it out",
+                    self.configuration
+                );
+                return;
+            };
+            log::warn!("entertainment stream dropped without being closed, stopping it");
+            let bridge = self.bridge.clone();
+            let configuration = std::mem::take(&mut self.configuration);
+            runtime.spawn(async move {
+                if let Err(e) = bridge.stop_entertainment(&configuration).await {
+                    log::warn!("failed to stop entertainment configuration {configuration}: {e}");
+                }
+            });
+        }
+    }
+
+    fn handshake(
+        addr: SocketAddr,
+        identity: String,
+        psk: Vec<u8>,
+    ) -> crate::Result<SslStream<UdpChannel>> {
+        let socket = UdpSocket::bind(match addr {
+            SocketAddr::V4(_) => "0.0.0.0:0",
+            SocketAddr::V6(_) => "[::]:0",
+        })
+        .map_err(stream_err)?;
+        socket.connect(addr).map_err(stream_err)?;
+        socket
+            .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .map_err(stream_err)?;
+
+        let mut context = SslContext::builder(SslMethod::dtls_client()).map_err(stream_err)?;
+        context
+            .set_min_proto_version(Some(SslVersion::DTLS1_2))
+            .map_err(stream_err)?;
+        context
+            .set_max_proto_version(Some(SslVersion::DTLS1_2))
+            .map_err(stream_err)?;
+        // the only cipher suite supported by the bridge
+        context
+            .set_cipher_list("PSK-AES128-GCM-SHA256")
+            .map_err(stream_err)?;
+        context.set_psk_client_callback(move |_, _, identity_buf, psk_buf| {
+            let identity = identity.as_bytes();
+            if identity.len() >= identity_buf.len() || psk.len() > psk_buf.len() {
+                return Err(openssl::error::ErrorStack::get());
+            }
+            identity_buf[..identity.len()].copy_from_slice(identity);
+            identity_buf[identity.len()] = 0;
+            psk_buf[..psk.len()].copy_from_slice(&psk);
+            Ok(psk.len())
+        });
+        let mut ssl = Ssl::new(&context.build()).map_err(stream_err)?;
+        ssl.set_mtu(MTU).map_err(stream_err)?;
+        let mut stream = SslStream::new(ssl, UdpChannel(socket)).map_err(stream_err)?;
+        stream.connect().map_err(stream_err)?;
+        stream
+            .get_ref()
+            .0
+            .set_read_timeout(None)
+            .map_err(stream_err)?;
+        Ok(stream)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::XY;
+
+        // A stand-in for the bridge, accepting one DTLS session and returning the first record
+        fn serve_one(socket: UdpSocket, identity: &'static str, psk: Vec<u8>) -> Vec<u8> {
+            let (_, client) = socket.peek_from(&mut [0; 1]).unwrap();
+            socket.connect(client).unwrap();
+            let mut context = SslContext::builder(SslMethod::dtls_server()).unwrap();
+            context.set_cipher_list("PSK-AES128-GCM-SHA256").unwrap();
+            context.set_psk_server_callback(move |_, client_identity, psk_buf| {
+                assert_eq!(client_identity, Some(identity.as_bytes()));
+                psk_buf[..psk.len()].copy_from_slice(&psk);
+                Ok(psk.len())
+            });
+            let mut ssl = Ssl::new(&context.build()).unwrap();
+            ssl.set_mtu(MTU).unwrap();
+            let mut stream = SslStream::new(ssl, UdpChannel(socket)).unwrap();
+            stream.accept().unwrap();
+            let mut buf = [0; MTU as usize];
+            let len = stream.ssl_read(&mut buf).unwrap();
+            buf[..len].to_vec()
+        }
+
+        #[tokio::test]
+        async fn test_stream_frame() {
+            let configuration = "1a8d99cc-967b-44f2-9202-43f976c0fa6b";
+            let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let addr = socket.local_addr().unwrap();
+            let server = std::thread::spawn(move || {
+                serve_one(
+                    socket,
+                    "application",
+                    decode_hex("0123456789abcdef").unwrap(),
+                )
+            });
+            let bridge = Bridge::for_ip([127u8, 0, 0, 1]).with_credentials(crate::Credentials {
+                application_key: "application".into(),
+                client_key: Some("0123456789ABCDEF".into()),
+            });
+            let mut stream = EntertainmentStream::connect(bridge, addr, configuration, 50)
+                .await
+                .unwrap();
+            let frame = EntertainmentFrame::Xy(vec![(3, XY { x: 0.5, y: 0.25 }, 1.0)]);
+            stream.send(&frame).await.unwrap();
+            assert_eq!(
+                server.join().unwrap(),
+                frame.encode(configuration, 0).unwrap()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_frame() {
+        let configuration = "1a8d99cc-967b-44f2-9202-43f976c0fa6b";
+        let frame = EntertainmentFrame::Rgb(vec![(0, [1.0, 0.0, 0.5]), (7, [0.0, 2.0, -1.0])]);
+        let message = frame.encode(configuration, 9).unwrap();
+        assert_eq!(&message[..9], b"HueStream");
+        assert_eq!(&message[9..16], &[2, 0, 9, 0, 0, 0, 0]);
+        assert_eq!(&message[16..52], configuration.as_bytes());
+        assert_eq!(
+            &message[52..],
+            &[0, 0xff, 0xff, 0, 0, 0x80, 0, 7, 0, 0, 0xff, 0xff, 0, 0]
+        );
+        let frame = EntertainmentFrame::Rgb(vec![(0, [0.0; 3]); 21]);
+        assert!(frame.encode(configuration, 0).is_err());
+    }
+
+    #[test]
+    fn test_configuration_geometry() {
+        let configuration: EntertainmentConfiguration = serde_json::from_str(
+            r#"{"id":"c1","metadata":{"name":"TV"},"configuration_type":"screen",
+            "status":"inactive","stream_proxy":{"mode":"auto","node":{"rid":"e1","rtype":"entertainment"}},
+            "channels":[
+                {"channel_id":0,"position":{"x":-0.8,"y":0.8,"z":0.0},
+                "members":[{"service":{"rid":"e1","rtype":"entertainment"},"index":0}]},
+                {"channel_id":1,"position":{"x":0.8,"y":0.8,"z":0.0},
+                "members":[{"service":{"rid":"e1","rtype":"entertainment"},"index":1}]},
+                {"channel_id":2,"position":{"x":0.0,"y":-1.0,"z":0.5},
+                "members":[{"service":{"rid":"e2","rtype":"entertainment"},"index":0}]}],
+            "locations":{"service_locations":[
+                {"service":{"rid":"e1","rtype":"entertainment"},"position":{"x":-0.8,"y":0.8,"z":0.0},
+                "positions":[{"x":-0.8,"y":0.8,"z":0.0},{"x":0.8,"y":0.8,"z":0.0}],
+                "equalization_factor":1.0}]},
+            "light_services":[{"rid":"l1","rtype":"light"}],"type":"entertainment_configuration"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            configuration.configuration_type,
+            EntertainmentConfigurationType::Screen
+        );
+        assert_eq!(configuration.channels_of("e1").len(), 2);
+        assert_eq!(configuration.channel(2).unwrap().position.z, 0.5);
+        assert_eq!(configuration.location("e1").unwrap().positions.len(), 2);
+        assert!(configuration.location("e2").is_none());
+
+        let command = CommandCreateEntertainmentConfiguration::new(
+            "Desk",
+            EntertainmentConfigurationType::Space3d,
+        )
+        .with_location(ServiceLocation::new(
+            "e2",
+            vec![Position {
+                x: 0.0,
+                y: 1.0,
+                z: -0.5,
+            }],
+        ));
+        assert_eq!(
+            serde_json::to_value(&command).unwrap(),
+            serde_json::json!({
+                "metadata": {"name": "Desk"},
+                "configuration_type": "3dspace",
+                "locations": {"service_locations": [{
+                    "service": {"rid": "e2", "rtype": "entertainment"},
+                    "positions": [{"x": 0.0, "y": 1.0, "z": -0.5}]
+                }]}
+            })
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use crate::{
+    ButtonState, CommandLightColor, CommandLightDimming, GroupedLight, Light, LightLevelState,
+    MotionState, On, RelativeRotaryState, ResourceIdentifier, SceneMetadata, SceneStatus,
+    TemperatureState,
+};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+
+/// An item of the bridge event stream, as returned by `Bridge::events`.
+#[derive(Debug, Clone)]
+pub enum HueEvent {
+    Event(EventEnvelope),
+    /// The stream lost its connection to the bridge and reconnected. Events emitted by the bridge
+    /// in between are lost, so any state derived from the stream should be refreshed.
+    Reconnected,
+    /// The full state of the bridge, fetched right after `Reconnected` when
+    /// `EventStreamOptions::resync` is set.
+    Resync(Resync),
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Resync {
+    pub lights: Vec<Light>,
+    pub grouped_lights: Vec<GroupedLight>,
+}
+
+/// Configures how `Bridge::events_with_options` reconnects to the bridge.
+#[derive(Debug, Clone)]
+pub struct EventStreamOptions {
+    /// Delay before the first reconnection attempt.
+    pub initial_delay: Duration,
+    /// Factor applied to the delay after each failed attempt.
+    pub factor: f64,
+    /// Upper bound of the delay between two attempts.
+    pub max_delay: Duration,
+    /// Whether to fetch lights and grouped lights after each reconnection.
+    pub resync: bool,
+}
+
+impl Default for EventStreamOptions {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            factor: 2.0,
+            max_delay: Duration::from_secs(30),
+            resync: false,
+        }
+    }
+}
+
+impl EventStreamOptions {
+    pub fn with_backoff(self, initial_delay: Duration, factor: f64, max_delay: Duration) -> Self {
+        Self {
+            initial_delay,
+            factor,
+            max_delay,
+            ..self
+        }
+    }
+
+    pub fn with_resync(self) -> Self {
+        Self {
+            resync: true,
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    Add,
+    Update,
+    Delete,
+    Error,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A batch of changes, as sent by the bridge. A single message of the event stream may hold
+/// several envelopes.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventEnvelope {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    /// Time at which the bridge emitted the event.
+    pub creationtime: String,
+    pub data: Vec<EventData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventColorTemperature {
+    pub mirek: Option<u16>,
+    pub mirek_valid: bool,
+}
+
+/// The changed properties of a light. For `EventKind::Add` this is the complete resource, for
+/// updates only the properties that changed are present.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub on: Option<On>,
+    pub dimming: Option<CommandLightDimming>,
+    pub color_temperature: Option<EventColorTemperature>,
+    pub color: Option<CommandLightColor>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupedLightUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub on: Option<On>,
+    pub dimming: Option<CommandLightDimming>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MotionUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub enabled: Option<bool>,
+    pub motion: Option<MotionState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub enabled: Option<bool>,
+    pub temperature: Option<TemperatureState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightLevelUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub enabled: Option<bool>,
+    pub light: Option<LightLevelState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub button: Option<ButtonState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelativeRotaryUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub relative_rotary: Option<RelativeRotaryState>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub group: Option<ResourceIdentifier>,
+    pub metadata: Option<SceneMetadata>,
+    pub status: Option<SceneStatus>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryState {
+    Normal,
+    Low,
+    Critical,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerState {
+    pub battery_state: Option<BatteryState>,
+    /// Remaining battery, in percent.
+    pub battery_level: Option<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DevicePowerUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub power_state: Option<PowerState>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectivityStatus {
+    Connected,
+    Disconnected,
+    ConnectivityIssue,
+    UnidirectionalIncoming,
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZigbeeConnectivityUpdate {
+    pub id: String,
+    pub id_v1: Option<String>,
+    pub owner: Option<ResourceIdentifier>,
+    pub status: Option<ConnectivityStatus>,
+    pub mac_address: Option<String>,
+}
+
+/// The resource a change applies to, decoded according to its `type`.
+#[derive(Debug, Clone)]
+pub enum EventData {
+    Light(LightUpdate),
+    GroupedLight(GroupedLightUpdate),
+    Motion(MotionUpdate),
+    Temperature(TemperatureUpdate),
+    LightLevel(LightLevelUpdate),
+    Button(ButtonUpdate),
+    RelativeRotary(RelativeRotaryUpdate),
+    Scene(SceneUpdate),
+    DevicePower(DevicePowerUpdate),
+    ZigbeeConnectivity(ZigbeeConnectivityUpdate),
+    /// A resource type this library does not model, or a payload that did not match its model.
+    Unknown(Value),
+}
+
+impl EventData {
+    /// Returns the id of the resource this change applies to.
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            EventData::Light(update) => Some(&update.id),
+            EventData::GroupedLight(update) => Some(&update.id),
+            EventData::Motion(update) => Some(&update.id),
+            EventData::Temperature(update) => Some(&update.id),
+            EventData::LightLevel(update) => Some(&update.id),
+            EventData::Button(update) => Some(&update.id),
+            EventData::RelativeRotary(update) => Some(&update.id),
+            EventData::Scene(update) => Some(&update.id),
+            EventData::DevicePower(update) => Some(&update.id),
+            EventData::ZigbeeConnectivity(update) => Some(&update.id),
+            EventData::Unknown(value) => value.get("id").and_then(Value::as_str),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EventData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        fn typed<T: DeserializeOwned>(value: Value, f: fn(T) -> EventData) -> EventData {
+            match serde_json::from_value::<T>(value.clone()) {
+                Ok(update) => f(update),
+                Err(e) => {
+                    log::debug!("could not decode event data {value}: {e}");
+                    EventData::Unknown(value)
+                }
+            }
+        }
+        let value = Value::deserialize(deserializer)?;
+        let rtype = value.get("type").and_then(Value::as_str).unwrap_or("");
+        Ok(match rtype {
+            "light" => typed(value, EventData::Light),
+            "grouped_light" => typed(value, EventData::GroupedLight),
+            "motion" => typed(value, EventData::Motion),
+            "temperature" => typed(value, EventData::Temperature),
+            "light_level" => typed(value, EventData::LightLevel),
+            "button" => typed(value, EventData::Button),
+            "relative_rotary" => typed(value, EventData::RelativeRotary),
+            "scene" => typed(value, EventData::Scene),
+            "device_power" => typed(value, EventData::DevicePower),
+            "zigbee_connectivity" => typed(value, EventData::ZigbeeConnectivity),
+            _ => EventData::Unknown(value),
+        })
+    }
+}
+
+/// Decodes one message of the event stream into one event per envelope. An envelope that cannot
+/// be decoded becomes a `HueEvent::Error`, without losing the others.
+pub(crate) fn parse_message(data: &str) -> Vec<HueEvent> {
+    match serde_json::from_str::<Vec<Value>>(data) {
+        Ok(envelopes) => envelopes
+            .into_iter()
+            .map(|envelope| match serde_json::from_value(envelope) {
+                Ok(envelope) => HueEvent::Event(envelope),
+                Err(e) => HueEvent::Error(format!("{:?}", e)),
+            })
+            .collect(),
+        Err(e) => vec![HueEvent::Error(format!("{:?}", e))],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ButtonEvent;
+
+    #[test]
+    fn test_parse_message() {
+        let message = r#"[
+            {"creationtime":"2024-01-01T10:00:00Z","id":"a","type":"update","data":[
+                {"id":"l1","id_v1":"/lights/1","on":{"on":true},"owner":{"rid":"d1","rtype":"device"},"type":"light"},
+                {"id":"b1","button":{"button_report":{"event":"initial_press","updated":"2024-01-01T10:00:00Z"},"last_event":"initial_press"},"owner":{"rid":"d2","rtype":"device"},"type":"button"},
+                {"id":"x1","type":"geofence_client"}
+            ]},
+            {"creationtime":"2024-01-01T10:00:01Z","id":"b","type":"delete","data":[
+                {"id":"s1","type":"scene"}
+            ]}
+        ]"#;
+        let events = parse_message(message);
+        assert_eq!(events.len(), 2);
+        let HueEvent::Event(envelope) = &events[0] else {
+            panic!("expected an event, got {:?}", events[0]);
+        };
+        assert_eq!(envelope.kind, EventKind::Update);
+        assert_eq!(envelope.creationtime, "2024-01-01T10:00:00Z");
+        assert!(matches!(&envelope.data[0], EventData::Light(l) if l.on.as_ref().unwrap().on));
+        assert!(matches!(&envelope.data[1], EventData::Button(b)
+            if b.button.as_ref().unwrap().last_event == Some(ButtonEvent::InitialPress)));
+        assert!(matches!(&envelope.data[2], EventData::Unknown(_)));
+        assert_eq!(envelope.data[2].id(), Some("x1"));
+        let HueEvent::Event(envelope) = &events[1] else {
+            panic!("expected an event, got {:?}", events[1]);
+        };
+        assert_eq!(envelope.kind, EventKind::Delete);
+        assert!(matches!(&envelope.data[0], EventData::Scene(s) if s.id == "s1"));
+    }
+
+    #[test]
+    fn test_parse_message_keeps_valid_envelopes() {
+        let message = r#"[
+            {"id":"a","type":"update","data":[{"id":"l1","type":"light"}]},
+            {"creationtime":"2024-01-01T10:00:01Z","id":"b","type":"delete","data":[
+                {"id":"s1","type":"scene"}
+            ]}
+        ]"#;
+        let events = parse_message(message);
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], HueEvent::Error(_)));
+        assert!(matches!(&events[1], HueEvent::Event(e) if e.kind == EventKind::Delete));
+    }
+}
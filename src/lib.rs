@@ -6,6 +6,7 @@
 //! ```no_run
 //! # tokio_test::block_on(async {
 //! let bridge = hueclient::Bridge::discover_required()
+//!     .await
 //!     .register_application("mycomputer") // Press the bridge before running this
 //!     .await
 //!     .unwrap();
@@ -14,15 +15,19 @@
 //! ```
 //! ### Second run
 //! ```no_run
+//! # tokio_test::block_on(async {
 //! const USERNAME: &str = "the username that was generated in the previous example";
 //! let bridge = hueclient::Bridge::discover_required()
+//!    .await
 //!    .with_user(USERNAME);
+//! # })
 //! ```
 //! ### Good night
 //! ```no_run
 //! # tokio_test::block_on(async {
 //! # const USERNAME: &str = "the username that was generated in the previous example";
 //! # let bridge = hueclient::Bridge::discover_required()
+//! #   .await
 //! #   .with_user(USERNAME);
 //! let cmd = hueclient::CommandLight::default().off();
 //! for light in &bridge.get_all_lights().await.unwrap() {
@@ -36,7 +41,7 @@
 pub enum HueError {
     /// Returned when a network error occurs.
     #[error("An error occurred while performing an HTTP request")]
-    Reqwest(#[from] reqwest::Error),
+    Reqwest(#[source] reqwest::Error),
     #[error("An error occurred while creating an event source")]
     ReqwestEventSource(#[from] reqwest_eventsource::CannotCloneRequestError),
     /// Returned on a JSON failure, which will usually be a problem with deserializing the bridge
@@ -65,6 +70,33 @@ pub enum HueError {
         /// An error message describing the failure.
         description: String,
     },
+    /// Returned when a command is rejected before being sent, because the target does not
+    /// support it.
+    #[error("Invalid command: {}", msg)]
+    InvalidCommand {
+        /// An error message describing the failure.
+        msg: String,
+    },
+    /// Returned when the certificate presented by the bridge is not signed by the Hue root CA,
+    /// does not match the expected bridge id, or does not match the pinned certificate.
+    #[error("The bridge could not be authenticated: {}", msg)]
+    BridgeIdentityMismatch {
+        /// An error message describing the failure.
+        msg: String,
+    },
+    /// Returned when the button of the bridge was not pressed before registering an application
+    /// timed out.
+    #[error("The bridge button was not pressed within {:?}", timeout)]
+    RegistrationTimeout {
+        /// How long the registration was retried.
+        timeout: std::time::Duration,
+    },
+    /// Returned when the DTLS session of an entertainment stream fails.
+    #[error("An entertainment streaming error occurred: {}", msg)]
+    EntertainmentError {
+        /// An error message describing the failure.
+        msg: String,
+    },
     /// Returned when discovering a bridge in the local network fails.
     #[error("A discovery error occurred: {}", msg)]
     DiscoveryError {
@@ -79,14 +111,39 @@ impl HueError {
             msg: err.to_string(),
         }
     }
+
+    pub(crate) fn invalid_command(err: impl std::fmt::Display) -> Self {
+        Self::InvalidCommand {
+            msg: err.to_string(),
+        }
+    }
+}
+
+impl From<reqwest::Error> for HueError {
+    fn from(err: reqwest::Error) -> Self {
+        match tls::identity_mismatch(&err) {
+            Some(msg) => Self::BridgeIdentityMismatch { msg },
+            None => Self::Reqwest(err),
+        }
+    }
 }
 
 /// A type alias used for convenience and consiceness throughout the library.
 pub type Result<T> = std::result::Result<T, HueError>;
 
 mod bridge;
+pub mod color;
 mod command_parser;
 mod disco;
+mod entertainment;
+mod event;
+mod state;
+mod tls;
 
 pub use bridge::*;
 pub use command_parser::*;
+pub use disco::{DiscoveredBridge, DiscoveryOptions};
+pub use entertainment::*;
+pub use event::*;
+pub use state::*;
+pub use tls::{CertificatePin, CertificatePolicy};
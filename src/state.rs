@@ -0,0 +1,404 @@
+use crate::{
+    Bridge, Device, Dimming, EventData, EventKind, EventStreamOptions, GroupedLight, HueEvent,
+    Light, Room, Scene, Zone,
+};
+use futures::{Stream, StreamExt};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Default)]
+struct Snapshot {
+    devices: BTreeMap<String, Device>,
+    lights: BTreeMap<String, Light>,
+    rooms: BTreeMap<String, Room>,
+    zones: BTreeMap<String, Zone>,
+    scenes: BTreeMap<String, Scene>,
+    grouped_lights: BTreeMap<String, GroupedLight>,
+}
+
+impl Snapshot {
+    async fn fetch(bridge: &Bridge) -> crate::Result<Self> {
+        Ok(Self {
+            devices: index(bridge.get_all_devices().await?, |d| &d.id),
+            lights: index(bridge.get_all_lights().await?, |l| &l.id),
+            rooms: index(bridge.get_all_rooms().await?, |r| &r.id),
+            zones: index(bridge.get_all_zones().await?, |z| &z.id),
+            scenes: index(bridge.get_all_scenes().await?, |s| &s.id),
+            grouped_lights: index(bridge.get_all_grouped_lights().await?, |g| &g.id),
+        })
+    }
+
+    /// Applies a change to the snapshot. Returns true if the change could not be applied
+    /// in place and the snapshot must be fetched again.
+    fn apply(&mut self, kind: EventKind, data: &EventData) -> bool {
+        match (kind, data) {
+            (EventKind::Delete, data) => {
+                if let Some(id) = data.id() {
+                    self.devices.remove(id);
+                    self.lights.remove(id);
+                    self.rooms.remove(id);
+                    self.zones.remove(id);
+                    self.scenes.remove(id);
+                    self.grouped_lights.remove(id);
+                }
+                false
+            }
+            (EventKind::Add, _) => true,
+            (EventKind::Update, EventData::Light(update)) => {
+                if let Some(light) = self.lights.get_mut(&update.id) {
+                    if let Some(on) = &update.on {
+                        light.on = on.clone();
+                    }
+                    if let (Some(dimming), Some(update)) = (&mut light.dimming, &update.dimming) {
+                        dimming.brightness = update.brightness;
+                    }
+                    if let (Some(ct), Some(update)) =
+                        (&mut light.color_temperature, &update.color_temperature)
+                    {
+                        ct.mirek = update.mirek;
+                        ct.mirek_valid = update.mirek_valid;
+                    }
+                    if let (Some(color), Some(update)) = (&mut light.color, &update.color) {
+                        color.xy = update.xy.clone();
+                    }
+                }
+                false
+            }
+            (EventKind::Update, EventData::GroupedLight(update)) => {
+                if let Some(group) = self.grouped_lights.get_mut(&update.id) {
+                    if let Some(on) = &update.on {
+                        group.on = Some(on.clone());
+                    }
+                    if let Some(dimming) = &update.dimming {
+                        group.dimming = Some(Dimming {
+                            brightness: dimming.brightness,
+                            min_dim_level: group.dimming.as_ref().and_then(|d| d.min_dim_level),
+                        });
+                    }
+                }
+                false
+            }
+            (EventKind::Update, EventData::Scene(update)) => {
+                if let Some(scene) = self.scenes.get_mut(&update.id) {
+                    if let Some(metadata) = &update.metadata {
+                        scene.metadata = metadata.clone();
+                    }
+                    if let Some(status) = &update.status {
+                        scene.status = Some(status.clone());
+                    }
+                }
+                false
+            }
+            // membership changes of devices, rooms and zones are not decoded, so fetch them again
+            (EventKind::Update, EventData::Unknown(value)) => matches!(
+                value.get("type").and_then(|t| t.as_str()),
+                Some("device" | "room" | "zone")
+            ),
+            _ => false,
+        }
+    }
+}
+
+fn index<T>(items: Vec<T>, id: impl Fn(&T) -> &String) -> BTreeMap<String, T> {
+    items
+        .into_iter()
+        .map(|item| (id(&item).clone(), item))
+        .collect()
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    snapshot: RwLock<Snapshot>,
+    watchers: Mutex<HashMap<String, watch::Sender<()>>>,
+}
+
+impl Shared {
+    fn notify<'a>(&self, ids: impl IntoIterator<Item = &'a str>) {
+        let watchers = self.watchers.lock().unwrap();
+        for id in ids {
+            if let Some(watcher) = watchers.get(id) {
+                watcher.send_replace(());
+            }
+        }
+    }
+
+    fn notify_all(&self) {
+        for watcher in self.watchers.lock().unwrap().values() {
+            watcher.send_replace(());
+        }
+    }
+
+    fn refresh(&self, snapshot: crate::Result<Snapshot>) {
+        match snapshot {
+            Ok(snapshot) => {
+                *self.snapshot.write().unwrap() = snapshot;
+                self.notify_all();
+            }
+            Err(e) => log::warn!("failed to refresh bridge state: {e}"),
+        }
+    }
+
+    async fn run<F: Future<Output = crate::Result<Snapshot>>>(
+        &self,
+        events: impl Stream<Item = HueEvent>,
+        fetch: impl Fn() -> F,
+    ) {
+        let mut events = Box::pin(events);
+        while let Some(event) = events.next().await {
+            match event {
+                HueEvent::Event(envelope) => {
+                    let mut refresh = false;
+                    {
+                        let mut snapshot = self.snapshot.write().unwrap();
+                        for data in &envelope.data {
+                            refresh |= snapshot.apply(envelope.kind, data);
+                        }
+                    }
+                    if refresh {
+                        self.refresh(fetch().await);
+                    } else {
+                        self.notify(envelope.data.iter().filter_map(EventData::id));
+                    }
+                }
+                HueEvent::Reconnected => self.refresh(fetch().await),
+                HueEvent::Resync(_) => {}
+                HueEvent::Error(e) => log::warn!("event stream error: {e}"),
+            }
+        }
+        log::error!("event stream ended, the bridge state is no longer updated");
+    }
+}
+
+// Waits until the event stream is connected, which `Bridge::event_stream` reports as its first
+// `Reconnected`. Fails on the first error, as the bridge is likely unreachable.
+async fn connected(events: &mut (impl Stream<Item = HueEvent> + Unpin)) -> crate::Result<()> {
+    while let Some(event) = events.next().await {
+        match event {
+            HueEvent::Reconnected => return Ok(()),
+            HueEvent::Error(e) => return Err(crate::HueError::protocol_err(e)),
+            _ => {}
+        }
+    }
+    Err(crate::HueError::protocol_err("event stream ended"))
+}
+
+/// An in-memory mirror of the bridge resources, kept up to date from the event stream. Lookups
+/// are synchronous and never hit the bridge.
+/// ### Example
+/// ```no_run
+/// # tokio_test::block_on(async {
+/// let bridge = hueclient::Bridge::for_ip([192u8, 168, 0, 4])
+///    .with_user("rVV05G0i52vQMMLn6BK3dpr0F3uDiqtDjPLPK2uj");
+/// let state = hueclient::BridgeState::new(&bridge).await.unwrap();
+/// let id = "6e9f8b1d-bd5b-44ff-a3a6-1e1d0a4e0d45";
+/// let mut changes = state.watch(id);
+/// while changes.changed().await.is_ok() {
+///     println!("{:?}", state.light(id).map(|light| light.on.on));
+/// }
+/// # })
+/// ```
+#[derive(Debug)]
+pub struct BridgeState {
+    shared: Arc<Shared>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl BridgeState {
+    /// Opens the event stream, then fetches every device, light, room, zone, scene and grouped
+    /// light of the bridge and spawns a task on the current tokio runtime applying the events to
+    /// them. Fetching only once the stream is connected makes sure no change is missed. The task
+    /// stops when the `BridgeState` is dropped.
+    pub async fn new(bridge: &Bridge) -> crate::Result<Self> {
+        let mut events = Box::pin(bridge.event_stream(EventStreamOptions::default(), true)?);
+        connected(&mut events).await?;
+        let shared = Arc::new(Shared {
+            snapshot: RwLock::new(Snapshot::fetch(bridge).await?),
+            ..Default::default()
+        });
+        let task = tokio::spawn({
+            let shared = shared.clone();
+            let bridge = bridge.clone();
+            async move {
+                let fetch = || Snapshot::fetch(&bridge);
+                shared.run(events, fetch).await
+            }
+        });
+        Ok(Self { shared, task })
+    }
+
+    /// Returns whether the event stream ended, after which lookups return the last known state.
+    /// The stream only ends when the bridge rejects the application key.
+    pub fn is_stale(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Returns a receiver notified each time the resource with this id changes.
+    pub fn watch(&self, id: &str) -> watch::Receiver<()> {
+        self.shared
+            .watchers
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_insert_with(|| watch::channel(()).0)
+            .subscribe()
+    }
+
+    pub fn device(&self, id: &str) -> Option<Device> {
+        self.shared
+            .snapshot
+            .read()
+            .unwrap()
+            .devices
+            .get(id)
+            .cloned()
+    }
+
+    /// Returns all devices, sorted by their id's.
+    pub fn devices(&self) -> Vec<Device> {
+        let snapshot = self.shared.snapshot.read().unwrap();
+        snapshot.devices.values().cloned().collect()
+    }
+
+    pub fn light(&self, id: &str) -> Option<Light> {
+        self.shared.snapshot.read().unwrap().lights.get(id).cloned()
+    }
+
+    /// Returns all lights, sorted by their id's.
+    pub fn lights(&self) -> Vec<Light> {
+        let snapshot = self.shared.snapshot.read().unwrap();
+        snapshot.lights.values().cloned().collect()
+    }
+
+    pub fn room(&self, id: &str) -> Option<Room> {
+        self.shared.snapshot.read().unwrap().rooms.get(id).cloned()
+    }
+
+    /// Returns all rooms, sorted by their id's.
+    pub fn rooms(&self) -> Vec<Room> {
+        let snapshot = self.shared.snapshot.read().unwrap();
+        snapshot.rooms.values().cloned().collect()
+    }
+
+    pub fn zone(&self, id: &str) -> Option<Zone> {
+        self.shared.snapshot.read().unwrap().zones.get(id).cloned()
+    }
+
+    /// Returns all zones, sorted by their id's.
+    pub fn zones(&self) -> Vec<Zone> {
+        let snapshot = self.shared.snapshot.read().unwrap();
+        snapshot.zones.values().cloned().collect()
+    }
+
+    pub fn scene(&self, id: &str) -> Option<Scene> {
+        self.shared.snapshot.read().unwrap().scenes.get(id).cloned()
+    }
+
+    /// Returns all scenes, sorted by their id's.
+    pub fn scenes(&self) -> Vec<Scene> {
+        let snapshot = self.shared.snapshot.read().unwrap();
+        snapshot.scenes.values().cloned().collect()
+    }
+
+    pub fn grouped_light(&self, id: &str) -> Option<GroupedLight> {
+        let snapshot = self.shared.snapshot.read().unwrap();
+        snapshot.grouped_lights.get(id).cloned()
+    }
+
+    /// Returns all grouped lights, sorted by their id's.
+    pub fn grouped_lights(&self) -> Vec<GroupedLight> {
+        let snapshot = self.shared.snapshot.read().unwrap();
+        snapshot.grouped_lights.values().cloned().collect()
+    }
+}
+
+impl Drop for BridgeState {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_light_update() {
+        let light: Light = serde_json::from_str(
+            r#"{"id":"l1","metadata":{"name":"Desk","archetype":"classic_bulb","function":"mixed"},
+            "service_id":0,"on":{"on":false},"dimming":{"brightness":20.0},
+            "color_temperature":{"mirek":null,"mirek_valid":false,
+            "mirek_schema":{"mirek_minimum":153,"mirek_maximum":500}}}"#,
+        )
+        .unwrap();
+        let mut snapshot = Snapshot {
+            lights: index(vec![light], |l| &l.id),
+            ..Default::default()
+        };
+        let update: EventData = serde_json::from_str(
+            r#"{"id":"l1","type":"light","on":{"on":true},"dimming":{"brightness":75.0},
+            "color_temperature":{"mirek":366,"mirek_valid":true}}"#,
+        )
+        .unwrap();
+        assert!(!snapshot.apply(EventKind::Update, &update));
+        let light = &snapshot.lights["l1"];
+        assert!(light.on.on);
+        assert_eq!(light.dimming.as_ref().unwrap().brightness, 75.0);
+        assert_eq!(light.color_temperature.as_ref().unwrap().mirek, Some(366));
+
+        let delete: EventData = serde_json::from_str(r#"{"id":"l1","type":"light"}"#).unwrap();
+        assert!(!snapshot.apply(EventKind::Delete, &delete));
+        assert!(snapshot.lights.is_empty());
+    }
+
+    #[test]
+    fn test_refresh_when_reconnected() {
+        let light = |on: bool| -> Light {
+            serde_json::from_value(serde_json::json!({"id":"l1",
+                "metadata":{"name":"Desk","archetype":"classic_bulb","function":"mixed"},
+                "service_id":0,"on":{"on":on}}))
+            .unwrap()
+        };
+        // the light was switched on while the stream was disconnected
+        let shared = Shared {
+            snapshot: RwLock::new(Snapshot {
+                lights: index(vec![light(false)], |l| &l.id),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let events = futures::stream::iter([HueEvent::Reconnected]);
+        let fetch = || async {
+            Ok(Snapshot {
+                lights: index(vec![light(true)], |l| &l.id),
+                ..Default::default()
+            })
+        };
+        tokio_test::block_on(shared.run(events, fetch));
+        assert!(shared.snapshot.read().unwrap().lights["l1"].on.on);
+    }
+
+    #[test]
+    fn test_connected() {
+        let mut events = futures::stream::iter([
+            HueEvent::Resync(crate::Resync {
+                lights: vec![],
+                grouped_lights: vec![],
+            }),
+            HueEvent::Reconnected,
+            HueEvent::Error("after".into()),
+        ]);
+        assert!(tokio_test::block_on(connected(&mut events)).is_ok());
+        // events received once connected are left for the mirror
+        assert!(matches!(
+            tokio_test::block_on(events.next()),
+            Some(HueEvent::Error(_))
+        ));
+
+        let mut events = futures::stream::iter([HueEvent::Error("unreachable".into())]);
+        assert!(tokio_test::block_on(connected(&mut events)).is_err());
+        let mut events = futures::stream::iter([]);
+        assert!(tokio_test::block_on(connected(&mut events)).is_err());
+    }
+}
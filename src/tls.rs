@@ -0,0 +1,311 @@
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::server::ParsedCertificate;
+use rustls::{CertificateError, DigitallySignedStruct, OtherError, RootCertStore, SignatureScheme};
+use sha2::{Digest, Sha256};
+use std::sync::{Arc, Mutex};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+// see https://developers.meethue.com/develop/application-design-guidance/using-https/
+const HUE_ROOT_CA: &[u8] = b"-----BEGIN CERTIFICATE-----
+MIICMjCCAdigAwIBAgIUO7FSLbaxikuXAljzVaurLXWmFw4wCgYIKoZIzj0EAwIw
+OTELMAkGA1UEBhMCTkwxFDASBgNVBAoMC1BoaWxpcHMgSHVlMRQwEgYDVQQDDAty
+b290LWJyaWRnZTAiGA8yMDE3MDEwMTAwMDAwMFoYDzIwMzgwMTE5MDMxNDA3WjA5
+MQswCQYDVQQGEwJOTDEUMBIGA1UECgwLUGhpbGlwcyBIdWUxFDASBgNVBAMMC3Jv
+b3QtYnJpZGdlMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAEjNw2tx2AplOf9x86
+aTdvEcL1FU65QDxziKvBpW9XXSIcibAeQiKxegpq8Exbr9v6LBnYbna2VcaK0G22
+jOKkTqOBuTCBtjAPBgNVHRMBAf8EBTADAQH/MA4GA1UdDwEB/wQEAwIBhjAdBgNV
+HQ4EFgQUZ2ONTFrDT6o8ItRnKfqWKnHFGmQwdAYDVR0jBG0wa4AUZ2ONTFrDT6o8
+ItRnKfqWKnHFGmShPaQ7MDkxCzAJBgNVBAYTAk5MMRQwEgYDVQQKDAtQaGlsaXBz
+IEh1ZTEUMBIGA1UEAwwLcm9vdC1icmlkZ2WCFDuxUi22sYpLlwJY81Wrqy11phcO
+MAoGCCqGSM49BAMCA0gAMEUCIEBYYEOsa07TH7E5MJnGw557lVkORgit2Rm1h3B2
+sFgDAiEA1Fj/C3AN5psFMjo0//mrQebo0eKd3aWRx+pQY08mk48=
+-----END CERTIFICATE-----";
+
+/// The SHA-256 fingerprint of the self-signed certificate a bridge presented the first time it was
+/// contacted. Certificates signed by the Hue root CA are never pinned. Clones share the same pin,
+/// so it can be read back after the first request and stored next to the application key.
+#[derive(Debug, Clone, Default)]
+pub struct CertificatePin(Arc<Mutex<Option<[u8; 32]>>>);
+
+impl CertificatePin {
+    /// Creates an empty pin, filled in by the first successful connection to a bridge with a
+    /// self-signed certificate.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restores a pin stored from a previous run.
+    pub fn from_fingerprint(fingerprint: [u8; 32]) -> Self {
+        Self(Arc::new(Mutex::new(Some(fingerprint))))
+    }
+
+    /// Returns the pinned fingerprint, if a certificate was seen yet.
+    pub fn fingerprint(&self) -> Option<[u8; 32]> {
+        *self.0.lock().unwrap()
+    }
+
+    // Pins the certificate if nothing is pinned yet, and checks it against the pin otherwise
+    fn check_or_pin(&self, cert: &CertificateDer<'_>) -> Result<(), IdentityMismatch> {
+        let fingerprint: [u8; 32] = Sha256::digest(cert.as_ref()).into();
+        let mut pinned = self.0.lock().unwrap();
+        match *pinned {
+            Some(pinned) if pinned != fingerprint => Err(IdentityMismatch(
+                "certificate does not match the pinned fingerprint".into(),
+            )),
+            Some(_) => Ok(()),
+            None => {
+                log::info!("pinning self-signed bridge certificate");
+                *pinned = Some(fingerprint);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// How the certificate presented by the bridge is verified.
+#[derive(Debug, Clone, Default)]
+pub enum CertificatePolicy {
+    /// Only accept certificates signed by the Signify root CA, which every bridge has used since
+    /// firmware 1.24.
+    #[default]
+    HueRootCa,
+    /// Accept certificates signed by the Signify root CA, or else the self-signed certificate of
+    /// older bridges, as long as it matches the pin. An empty pin is filled in by the first
+    /// connection.
+    TrustOnFirstUse(CertificatePin),
+}
+
+/// The bridge a client is expected to talk to.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BridgeIdentity {
+    /// The id of the bridge, expected as the common name of its certificate.
+    pub bridge_id: Option<String>,
+    pub policy: CertificatePolicy,
+}
+
+#[derive(Debug)]
+struct IdentityMismatch(String);
+
+impl std::fmt::Display for IdentityMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for IdentityMismatch {}
+
+impl From<IdentityMismatch> for rustls::Error {
+    fn from(value: IdentityMismatch) -> Self {
+        rustls::Error::InvalidCertificate(CertificateError::Other(OtherError(Arc::new(value))))
+    }
+}
+
+#[derive(Debug)]
+struct BridgeVerifier {
+    roots: RootCertStore,
+    identity: BridgeIdentity,
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl BridgeVerifier {
+    fn new(identity: BridgeIdentity) -> Self {
+        let mut roots = RootCertStore::empty();
+        let root = CertificateDer::from_pem_slice(HUE_ROOT_CA)
+            .expect("the hardcoded certificate should always parse");
+        roots
+            .add(root)
+            .expect("the hardcoded certificate should always be a valid trust anchor");
+        Self {
+            roots,
+            identity,
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        }
+    }
+
+    fn verify(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> Result<(), IdentityMismatch> {
+        let cert = ParsedCertificate::try_from(end_entity)
+            .map_err(|e| IdentityMismatch(format!("invalid certificate: {e}")))?;
+        let signed = rustls::client::verify_server_cert_signed_by_trust_anchor(
+            &cert,
+            &self.roots,
+            intermediates,
+            now,
+            self.algorithms.all,
+        );
+        let pin = match (signed, &self.identity.policy) {
+            (Ok(()), _) => None,
+            (Err(e), CertificatePolicy::TrustOnFirstUse(pin)) => {
+                log::debug!("bridge certificate is not signed by the Hue root CA: {e}");
+                Some(pin)
+            }
+            (Err(e), CertificatePolicy::HueRootCa) => Err(IdentityMismatch(format!(
+                "certificate is not signed by the Hue root CA: {e}"
+            )))?,
+        };
+        if let Some(bridge_id) = &self.identity.bridge_id {
+            let names = common_names(end_entity).unwrap_or_default();
+            if !names
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(bridge_id))
+            {
+                Err(IdentityMismatch(format!(
+                    "expected a certificate for bridge {bridge_id}, got one for {names:?}"
+                )))?
+            }
+        }
+        // only pin a certificate that passed every other check
+        if let Some(pin) = pin {
+            pin.check_or_pin(end_entity)?;
+        }
+        Ok(())
+    }
+}
+
+impl ServerCertVerifier for BridgeVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        // the bridge is reached by IP-address, its id is checked against the common name instead
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        self.verify(end_entity, intermediates, now)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+pub(crate) fn client_config(identity: &BridgeIdentity) -> rustls::ClientConfig {
+    let mut config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .expect("the ring provider supports the default protocol versions")
+    .dangerous()
+    .with_custom_certificate_verifier(Arc::new(BridgeVerifier::new(identity.clone())))
+    .with_no_client_auth();
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    config
+}
+
+/// Returns the reason the bridge certificate was rejected, if this is what made the request fail.
+pub(crate) fn identity_mismatch(err: &reqwest::Error) -> Option<String> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(err);
+    while let Some(err) = source {
+        if let Some(rustls::Error::InvalidCertificate(CertificateError::Other(other))) =
+            err.downcast_ref::<rustls::Error>()
+        {
+            return other
+                .0
+                .downcast_ref::<IdentityMismatch>()
+                .map(ToString::to_string);
+        }
+        // io::Error::source skips the error it wraps
+        source = match err
+            .downcast_ref::<std::io::Error>()
+            .and_then(|e| e.get_ref())
+        {
+            Some(inner) => Some(inner),
+            None => err.source(),
+        };
+    }
+    None
+}
+
+/// Returns the common names of the subject of a certificate.
+fn common_names(cert: &CertificateDer<'_>) -> Option<Vec<String>> {
+    let (_, cert) = X509Certificate::from_der(cert.as_ref()).ok()?;
+    cert.subject()
+        .iter_common_name()
+        .map(|name| name.as_str().ok().map(String::from))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SELF_SIGNED: &[u8] = b"-----BEGIN CERTIFICATE-----
+MIIB0jCCAXmgAwIBAgIUUUrsmSSQ9uv3vt5U4851BgBMa8owCgYIKoZIzj0EAwIw
+PjELMAkGA1UEBhMCTkwxFDASBgNVBAoMC1BoaWxpcHMgSHVlMRkwFwYDVQQDDBAw
+MDE3ODhmZmZlMDAwMDAxMCAXDTI2MTAxODA3MzQ1NloYDzIxMjYwOTI0MDczNDU2
+WjA+MQswCQYDVQQGEwJOTDEUMBIGA1UECgwLUGhpbGlwcyBIdWUxGTAXBgNVBAMM
+EDAwMTc4OGZmZmUwMDAwMDEwWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAS3W5C5
+MaWZH86VYUbNC1elSgYefSleVKTFNMkahZhzDdgGMArRa6OyE2i8yT1B8TYAqJQY
+mxyzqVr1kSoVofhqo1MwUTAdBgNVHQ4EFgQUfknkGJicQAlKJPUcC0dwX9TPJ/Aw
+HwYDVR0jBBgwFoAUfknkGJicQAlKJPUcC0dwX9TPJ/AwDwYDVR0TAQH/BAUwAwEB
+/zAKBggqhkjOPQQDAgNHADBEAiAsCZVK8F4Eyxf8jqDVlNrtFp+bI1LKZ5OrjL15
+8cGjoAIgZdNJBZxQmvd2tMwJc+VA1v3Rb+zKUrEMXTrnfiSg8Ps=
+-----END CERTIFICATE-----";
+
+    #[test]
+    fn test_verify_self_signed() {
+        let cert = CertificateDer::from_pem_slice(SELF_SIGNED).unwrap();
+        let root = CertificateDer::from_pem_slice(HUE_ROOT_CA).unwrap();
+        assert_eq!(
+            common_names(&cert),
+            Some(vec!["001788fffe000001".to_string()])
+        );
+        assert_eq!(common_names(&root), Some(vec!["root-bridge".to_string()]));
+
+        let verifier = |bridge_id: Option<&str>, policy| {
+            BridgeVerifier::new(BridgeIdentity {
+                bridge_id: bridge_id.map(String::from),
+                policy,
+            })
+        };
+        let now = UnixTime::now();
+        assert!(verifier(None, CertificatePolicy::HueRootCa)
+            .verify(&cert, &[], now)
+            .is_err());
+
+        let pin = CertificatePin::new();
+        let tofu = verifier(
+            Some("001788FFFE000001"),
+            CertificatePolicy::TrustOnFirstUse(pin.clone()),
+        );
+        assert!(tofu.verify(&cert, &[], now).is_ok());
+        assert!(pin.fingerprint().is_some());
+        assert!(tofu.verify(&cert, &[], now).is_ok());
+        assert!(tofu.verify(&root, &[], now).is_err());
+
+        let pin = CertificatePin::new();
+        let other = verifier(
+            Some("001788fffe000002"),
+            CertificatePolicy::TrustOnFirstUse(pin.clone()),
+        );
+        assert!(other.verify(&cert, &[], now).is_err());
+        assert!(pin.fingerprint().is_none());
+    }
+}